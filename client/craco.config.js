@@ -29,6 +29,14 @@ module.exports = {
         wasmLoader
       );
 
+      // Solves run in a worker (`src/App/solve.worker.ts`); workers need `this` rather than
+      // `window` as their global object.
+      webpackConfig.module.rules.unshift({
+        test: /\.worker\.ts$/,
+        use: { loader: 'worker-loader' },
+      });
+      webpackConfig.output.globalObject = 'this';
+
       return webpackConfig;
     },
   },
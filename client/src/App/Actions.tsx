@@ -1,5 +1,6 @@
 import React from 'react';
 import { CubeState, CUBE_FACELET_COLOURS } from '../constants';
+import type { SolveProgress } from 'wasm-rubik-cube-solver';
 import styles from './App.module.scss';
 
 type ActionsProps = {
@@ -7,6 +8,9 @@ type ActionsProps = {
   onStateChange: (state: CubeState) => void;
   onRandom: () => void;
   onSolve: () => void;
+  isSolving: boolean;
+  progress?: SolveProgress;
+  onCancel: () => void;
 };
 
 const Actions = ({
@@ -14,6 +18,9 @@ const Actions = ({
   onStateChange,
   onRandom,
   onSolve,
+  isSolving,
+  progress,
+  onCancel,
 }: ActionsProps) => {
   const unknownColours = new RegExp(
     `[^${Object.keys(CUBE_FACELET_COLOURS).join('')}]`,
@@ -34,7 +41,16 @@ const Actions = ({
       />
       <br />
       <button onClick={onRandom}>Random</button>
-      <button onClick={onSolve}>Solve</button>
+      {isSolving ? (
+        <>
+          <button onClick={onCancel}>Cancel</button>
+          <span className={styles.Progress}>
+            Solving{progress && ` (phase ${progress.phasesSolved + 1} of 4)`}
+          </span>
+        </>
+      ) : (
+        <button onClick={onSolve}>Solve</button>
+      )}
     </div>
   );
 };
@@ -14,41 +14,84 @@ import {
   Solution,
   SOLVED_CUBE,
 } from '../constants';
+import SolveWorker from './solve.worker';
+import type { SolveRequest, SolveResponse } from './solve.worker';
+import type { SolveProgress } from 'wasm-rubik-cube-solver';
 
 type AppProps = {
   solver: Solver;
 };
 
-const doSolve = (
+type SolveHandle = {
+  // Resolves to undefined once cancelled.
+  moves: Promise<Move[] | undefined>;
+  cancel: () => void;
+};
+
+// Solves in a worker so the page stays responsive; cancelling terminates the worker.
+const findSolution = (
+  state: CubeState,
+  onProgress: (progress: SolveProgress) => void
+): SolveHandle => {
+  const worker = new SolveWorker();
+  let cancel = () => {};
+  const moves = new Promise<Move[] | undefined>((resolve, reject) => {
+    cancel = () => {
+      worker.terminate();
+      resolve(undefined);
+    };
+    worker.onmessage = ({ data }: MessageEvent<SolveResponse>) => {
+      if (data.type === 'progress') {
+        onProgress(data.progress);
+        return;
+      }
+      worker.terminate();
+      if (data.type === 'solved') {
+        resolve(data.moves);
+      } else {
+        reject(new Error(data.message));
+      }
+    };
+    worker.onerror = event => {
+      worker.terminate();
+      reject(new Error(event.message));
+    };
+    const request: SolveRequest = { state };
+    worker.postMessage(request);
+  });
+
+  return { moves, cancel };
+};
+
+const toSolution = (
   solver: Solver,
-  state: CubeState
-): Promise<Solution> => {
-  try {
-    const solution = solver.solve_cube(state);
-
-    return Promise.resolve([
-      ...solution.map(
-        (move: Move, idx): MoveTransition => ({
-          move,
-          startState: solver.apply_cube_moves(
-            state,
-            solution.slice(0, idx)
-          ),
-          endState: solver.apply_cube_moves(
-            state,
-            solution.slice(0, idx + 1)
-          ),
-        })
-      ),
-      {
-        move: '🎉',
-        startState: SOLVED_CUBE,
-        endState: SOLVED_CUBE,
-      },
-    ]);
-  } catch (error) {
-    return Promise.reject(error);
-  }
+  state: CubeState,
+  moves: Move[]
+): Solution => {
+  let cube = solver.Cube.fromFacelets(state);
+  const transitions = moves.map(
+    (move: Move): MoveTransition => {
+      const next = cube.apply(move);
+      const transition = {
+        move,
+        startState: cube.toFacelets(),
+        endState: next.toFacelets(),
+      };
+      cube.free();
+      cube = next;
+      return transition;
+    }
+  );
+  cube.free();
+
+  return [
+    ...transitions,
+    {
+      move: '🎉',
+      startState: SOLVED_CUBE,
+      endState: SOLVED_CUBE,
+    },
+  ];
 };
 
 const App = ({ solver }: AppProps) => {
@@ -57,6 +100,10 @@ const App = ({ solver }: AppProps) => {
   const [solution, setSolution] = useState<Solution>([]);
   const [solutionIdx, setSolutionIdx] = useState(0);
   const [isAutoPlay, setAutoPlay] = useState(true);
+  const [solve, setSolve] = useState<SolveHandle>();
+  const [progress, setProgress] = useState<SolveProgress>();
+
+  useEffect(() => () => solve?.cancel(), [solve]);
 
   useEffect(() => {
     if (!cubeRef.current) return;
@@ -87,20 +134,30 @@ const App = ({ solver }: AppProps) => {
   }, [solution, solutionIdx, isAutoPlay]);
 
   const handleRandom = () => {
-    setState(solver.rand_cube());
+    solve?.cancel();
+    const cube = solver.Cube.random();
+    setState(cube.toFacelets());
+    cube.free();
     setSolution([]);
     setSolutionIdx(0);
   };
 
   const handleSolve = () => {
-    doSolve(solver, state)
-      .then(solution => {
-        setSolution(solution);
+    const handle = findSolution(state, setProgress);
+    setSolve(handle);
+    setProgress(undefined);
+    handle.moves
+      .then(moves => {
+        if (!moves) return;
+        setSolution(toSolution(solver, state, moves));
         setSolutionIdx(0);
         setAutoPlay(true);
       })
-      .catch(error => {
-        global.alert(error);
+      .catch((error: Error) => {
+        global.alert(error.message);
+      })
+      .finally(() => {
+        setSolve(current => (current === handle ? undefined : current));
       });
   };
 
@@ -120,6 +177,9 @@ const App = ({ solver }: AppProps) => {
         onStateChange={setState}
         onRandom={handleRandom}
         onSolve={handleSolve}
+        isSolving={solve !== undefined}
+        progress={progress}
+        onCancel={() => solve?.cancel()}
       />
       <Canvas
         onCreated={({ gl }) => {
@@ -0,0 +1,48 @@
+import type { CubeState, Move } from '../constants';
+import type {
+  IncrementalSolver,
+  SolveProgress,
+} from 'wasm-rubik-cube-solver';
+
+export type SolveRequest = {
+  state: CubeState;
+};
+
+export type SolveResponse =
+  | { type: 'progress'; progress: SolveProgress }
+  | { type: 'solved'; moves: Move[] }
+  | { type: 'failed'; message: string };
+
+// The number of states searched between progress reports.
+const SOLVE_STEP_BUDGET = 10000;
+
+// eslint-disable-next-line no-restricted-globals
+const ctx: Worker = self as any;
+
+ctx.onmessage = async ({ data }: MessageEvent<SolveRequest>) => {
+  const solver = await import('wasm-rubik-cube-solver');
+  const respond = (response: SolveResponse) => ctx.postMessage(response);
+
+  let incrementalSolver: IncrementalSolver | undefined;
+  try {
+    const cube = solver.Cube.fromFacelets(data.state);
+    incrementalSolver = new solver.IncrementalSolver(cube);
+    cube.free();
+
+    for (;;) {
+      const progress = incrementalSolver.step(SOLVE_STEP_BUDGET);
+      if (progress.solution) {
+        respond({ type: 'solved', moves: progress.solution.moves });
+        return;
+      }
+      respond({ type: 'progress', progress });
+    }
+  } catch (error) {
+    respond({ type: 'failed', message: error.message });
+  } finally {
+    incrementalSolver?.free();
+  }
+};
+
+// Lets the worker be imported as a constructor, which worker-loader provides.
+export default {} as typeof Worker & { new (): Worker };
@@ -55,6 +55,8 @@ export type Solution = MoveTransition[];
 
 export type Solver = typeof import('wasm-rubik-cube-solver');
 
+export type { CubeError } from 'wasm-rubik-cube-solver';
+
 export const SOLVED_CUBE =
   'WWWWWWWWWRRRRRRRRRGGGGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB';
 
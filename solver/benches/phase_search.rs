@@ -0,0 +1,80 @@
+//! Times solving three fixed cubes by searching each phase, and measures the peak heap each
+//! solve allocates.
+//!
+//! The embedded phase tables would skip the searches, so run without them:
+//!
+//! ```text
+//! cargo bench --no-default-features --bench phase_search
+//! ```
+//!
+//! Only `FaceletCube::solve` is used, so earlier revisions can be compared by running this file
+//! against them; the baseline needs `FaceletCube` exported with a `solve` method delegating to
+//! `pochmann_solver::solve`, which it did not have yet.
+
+#[cfg(not(feature = "wee_alloc"))]
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use wasm_rubik_cube_solver::FaceletCube;
+
+/// The scrambled cubes from the solver's tests.
+const CUBES: [&str; 3] = [
+    "OGOYWWWWYRBYRRRORRORBYGGWOBBWYBYYRWWWBBGOOGORGOGBBYGGY",
+    "BGYRWOYGOWYOWRRYYBOWGBGRBORYWGGYBRBWWYGBORWYRBOOGBORWG",
+    "BRGOWGWWBOOYRRBOGROOYOGYGRGYWWBYYRBBYBBGOYWWORGRYBWWRG",
+];
+
+const RUNS: u32 = 5;
+
+/// Counts the bytes allocated, remembering the most held at once.
+#[cfg(not(feature = "wee_alloc"))]
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(not(feature = "wee_alloc"))]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+// The `wee_alloc` feature installs its own allocator, leaving the peak unmeasured.
+#[cfg(not(feature = "wee_alloc"))]
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn main() {
+    for facelets in CUBES.iter() {
+        let cube: FaceletCube = facelets.parse().expect("Benchmark cubes are valid");
+
+        let mut fastest = Duration::MAX;
+        let mut peak = 0;
+        for _ in 0..RUNS {
+            PEAK.store(ALLOCATED.load(Ordering::SeqCst), Ordering::SeqCst);
+            let baseline = ALLOCATED.load(Ordering::SeqCst);
+
+            let started = Instant::now();
+            let solution = cube.solve().expect("Benchmark cubes are solvable");
+            fastest = fastest.min(started.elapsed());
+            peak = peak.max(PEAK.load(Ordering::SeqCst) - baseline);
+            drop(solution);
+        }
+
+        println!(
+            "{}  {:.3}s  peak {:.1} MiB",
+            facelets,
+            fastest.as_secs_f64(),
+            peak as f64 / (1024.0 * 1024.0)
+        );
+    }
+}
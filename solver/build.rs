@@ -0,0 +1,34 @@
+fn main() {
+    #[cfg(feature = "ffi")]
+    check_header();
+}
+
+/// Generates the C header declaring the `ffi` module's API into `OUT_DIR`, and fails the build
+/// when the checked in `include/rubik_cube_solver.h` differs from it. Building with
+/// `UPDATE_FFI_HEADER` set overwrites the checked in header instead.
+#[cfg(feature = "ffi")]
+fn check_header() {
+    use std::path::Path;
+
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=include/rubik_cube_solver.h");
+    println!("cargo:rerun-if-env-changed=UPDATE_FFI_HEADER");
+
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    let bindings = cbindgen::generate(&crate_dir).expect("Unable to generate the C header");
+    bindings.write_to_file(Path::new(&out_dir).join("rubik_cube_solver.h"));
+
+    let mut generated = Vec::new();
+    bindings.write(&mut generated);
+    let checked_in = Path::new(&crate_dir).join("include/rubik_cube_solver.h");
+    if std::env::var_os("UPDATE_FFI_HEADER").is_some() {
+        std::fs::write(&checked_in, generated).expect("Unable to update the C header");
+    } else if std::fs::read(&checked_in).ok() != Some(generated) {
+        panic!(
+            "include/rubik_cube_solver.h is out of date with src/ffi.rs; \
+             run `make ffi-header` and commit the result"
+        );
+    }
+}
@@ -0,0 +1,13 @@
+use std::env;
+
+use wasm_rubik_cube_solver::PhaseTables;
+
+fn main() {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "tables/phase_tables.bin".to_string());
+
+    PhaseTables::generate()
+        .save(&path)
+        .expect("Unable to write phase tables");
+}
@@ -0,0 +1,135 @@
+#ifndef RUBIK_CUBE_SOLVER_H
+#define RUBIK_CUBE_SOLVER_H
+
+/* Generated by cbindgen from src/ffi.rs; run `make ffi-header` to regenerate, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum RcsStatus {
+  RCS_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null.
+   */
+  RCS_STATUS_NULL_ARGUMENT = 1,
+  /**
+   * A string argument was not valid UTF-8.
+   */
+  RCS_STATUS_INVALID_UTF8 = 2,
+  RCS_STATUS_INVALID_FACELETS = 3,
+  RCS_STATUS_INVALID_MOVE = 4,
+  /**
+   * The cube can not be reached from solved.
+   */
+  RCS_STATUS_UNSOLVABLE_CUBE = 5,
+  /**
+   * The cube can not be solved using only the permitted moves.
+   */
+  RCS_STATUS_UNREACHABLE_CUBE = 6,
+} RcsStatus;
+
+/**
+ * A facelet cube, only ever handled through a pointer.
+ */
+typedef struct RcsCube RcsCube;
+
+typedef struct RcsSolveOptions {
+  /**
+   * Space separated moves the solution is restricted to (e.g. "R R' R2 U U' U2"), or null to
+   * permit every move. Only face turns are supported; slice moves such as M are rejected with
+   * `RCS_STATUS_INVALID_MOVE`.
+   */
+  const char *permitted_moves;
+} RcsSolveOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a solved cube.
+ */
+struct RcsCube *rcs_cube_new(void);
+
+/**
+ * Parses a cube from its 54 facelets, writing the new cube to `out` on success.
+ *
+ * # Safety
+ *
+ * `facelets` must be a null-terminated string and `out` must point to writable memory.
+ */
+enum RcsStatus rcs_cube_parse(const char *facelets, struct RcsCube **out);
+
+/**
+ * Releases a cube; null is ignored.
+ *
+ * # Safety
+ *
+ * `cube` must have come from this library and not already been freed.
+ */
+void rcs_cube_free(struct RcsCube *cube);
+
+/**
+ * The cube's 54 facelets, to be released with `rcs_string_free`; null if `cube` is null.
+ *
+ * # Safety
+ *
+ * `cube` must be null or a live cube from this library.
+ */
+char *rcs_cube_to_string(const struct RcsCube *cube);
+
+/**
+ * Checks the cube can be reached from solved, returning `RCS_STATUS_UNSOLVABLE_CUBE` if not.
+ *
+ * # Safety
+ *
+ * `cube` must be null or a live cube from this library.
+ */
+enum RcsStatus rcs_cube_validate(const struct RcsCube *cube);
+
+/**
+ * Applies space separated moves (e.g. "R U R' U'") to the cube, leaving it untouched if any
+ * move is invalid.
+ *
+ * # Safety
+ *
+ * `cube` must be null or a live cube from this library, and `moves` a null-terminated string.
+ */
+enum RcsStatus rcs_cube_apply_moves(struct RcsCube *cube, const char *moves);
+
+/**
+ * Solves the cube, writing the space separated solution to `solution` on success, to be
+ * released with `rcs_string_free`. `options` may be null to use the defaults.
+ *
+ * # Safety
+ *
+ * `cube` must be null or a live cube from this library, `options` null or a valid options
+ * struct, and `solution` must point to writable memory.
+ */
+enum RcsStatus rcs_cube_solve(const struct RcsCube *cube,
+                              const struct RcsSolveOptions *options,
+                              char **solution);
+
+/**
+ * Releases a string returned by this library; null is ignored.
+ *
+ * # Safety
+ *
+ * `s` must have come from this library and not already been freed.
+ */
+void rcs_string_free(char *s);
+
+/**
+ * Describes the last failure on the calling thread, or null if nothing has failed. The string
+ * is owned by the library and stays valid until the next failure on the same thread.
+ */
+const char *rcs_last_error(void);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* RUBIK_CUBE_SOLVER_H */
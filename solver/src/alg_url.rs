@@ -0,0 +1,173 @@
+//! Links to the scramble and solution on alg.cubing.net and Twizzle, and reading them back.
+//!
+//! alg.cubing.net writes spaces as `_` and primes as `-` to keep its URLs readable, percent
+//! encoding anything else outside of letters and digits, whereas Twizzle encodes its query as a
+//! form (spaces as `+`, primes as `%27`).
+
+use crate::moves::{Move, MoveSequence};
+
+const ALG_CUBING_NET: &str = "https://alg.cubing.net/";
+const TWIZZLE: &str = "https://alpha.twizzle.net/edit/";
+
+/// The setup moves (e.g. a scramble) and the algorithm (e.g. its solution) a link shows.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct SharedAlg {
+    pub setup: Vec<Move>,
+    pub alg: Vec<Move>,
+}
+
+fn percent_encode(s: &str, escape: impl Fn(char) -> Option<&'static str>) -> String {
+    let mut encoded = String::new();
+    for c in s.chars() {
+        if let Some(escaped) = escape(c) {
+            encoded.push_str(escaped);
+        } else if c.is_ascii_alphanumeric() {
+            encoded.push(c);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    encoded
+}
+
+fn percent_decode(s: &str, unescape: impl Fn(u8) -> u8) -> Result<String, &'static str> {
+    let mut bytes = vec![];
+    let mut input = s.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let hex: Vec<u8> = input.by_ref().take(2).collect();
+            let hex = std::str::from_utf8(&hex).map_err(|_| "Invalid percent encoding")?;
+            if hex.len() != 2 {
+                return Err("Invalid percent encoding");
+            }
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| "Invalid percent encoding")?);
+        } else {
+            bytes.push(unescape(byte));
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| "Invalid percent encoding")
+}
+
+/// Parses an algorithm as written on the sites, which may span several lines, carry `//`
+/// comments and write half turns as `R2'`.
+fn parse_alg(alg: &str) -> Result<Vec<Move>, &'static str> {
+    alg.lines()
+        .flat_map(|line| line.split("//").next().unwrap_or("").split_whitespace())
+        .map(|action| match action.strip_suffix("2'") {
+            Some(face) => format!("{}2", face).parse(),
+            None => action.parse(),
+        })
+        .collect()
+}
+
+/// Links to alg.cubing.net showing the setup followed by the algorithm.
+pub fn alg_cubing_net_url(setup: &[Move], alg: &[Move]) -> String {
+    let escape = |c: char| match c {
+        ' ' => Some("_"),
+        '\'' => Some("-"),
+        _ => None,
+    };
+    format!(
+        "{}?setup={}&alg={}",
+        ALG_CUBING_NET,
+        percent_encode(&MoveSequence::from(setup).to_string(), escape),
+        percent_encode(&MoveSequence::from(alg).to_string(), escape)
+    )
+}
+
+/// Links to Twizzle's editor showing the setup followed by the algorithm.
+pub fn twizzle_url(setup: &[Move], alg: &[Move]) -> String {
+    let escape = |c: char| match c {
+        ' ' => Some("+"),
+        _ => None,
+    };
+    format!(
+        "{}?setup-alg={}&alg={}",
+        TWIZZLE,
+        percent_encode(&MoveSequence::from(setup).to_string(), escape),
+        percent_encode(&MoveSequence::from(alg).to_string(), escape)
+    )
+}
+
+/// Reads the setup and algorithm back out of an alg.cubing.net or Twizzle link; either may be
+/// missing, leaving it empty.
+pub fn parse_alg_url(url: &str) -> Result<SharedAlg, &'static str> {
+    let (site, query) = url.split_once('?').ok_or("Link has no query")?;
+    let query = query.split('#').next().unwrap_or("");
+    let is_twizzle = site.contains("twizzle.net");
+    if !is_twizzle && !site.contains("alg.cubing.net") {
+        return Err("Link is not to alg.cubing.net or Twizzle");
+    }
+
+    let mut shared = SharedAlg {
+        setup: vec![],
+        alg: vec![],
+    };
+    for param in query.split('&') {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        let value = if is_twizzle {
+            percent_decode(value, |byte| if byte == b'+' { b' ' } else { byte })?
+        } else {
+            percent_decode(value, |byte| match byte {
+                b'_' => b' ',
+                b'-' => b'\'',
+                _ => byte,
+            })?
+        };
+        match (is_twizzle, key) {
+            (true, "setup-alg") | (false, "setup") => shared.setup = parse_alg(&value)?,
+            (_, "alg") => shared.alg = parse_alg(&value)?,
+            _ => {}
+        }
+    }
+    Ok(shared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(notation: &str) -> Vec<Move> {
+        notation.parse::<MoveSequence>().unwrap().0
+    }
+
+    #[test]
+    fn links_to_alg_cubing_net() {
+        let (setup, alg) = (moves("R U' F2"), moves("F2 U R'"));
+        let url = alg_cubing_net_url(&setup, &alg);
+
+        assert_eq!("https://alg.cubing.net/?setup=R_U-_F2&alg=F2_U_R-", url);
+        assert_eq!(Ok(SharedAlg { setup, alg }), parse_alg_url(&url));
+    }
+
+    #[test]
+    fn links_to_twizzle() {
+        let (setup, alg) = (moves("R U' F2"), moves("F2 U R'"));
+        let url = twizzle_url(&setup, &alg);
+
+        assert_eq!(
+            "https://alpha.twizzle.net/edit/?setup-alg=R+U%27+F2&alg=F2+U+R%27",
+            url
+        );
+        assert_eq!(Ok(SharedAlg { setup, alg }), parse_alg_url(&url));
+    }
+
+    #[test]
+    fn reads_links_written_by_hand() {
+        assert_eq!(
+            Ok(SharedAlg {
+                setup: vec![],
+                alg: moves("R U R' U' R2 D2"),
+            }),
+            parse_alg_url(
+                "https://alg.cubing.net/?alg=R_U_R-_%2F%2F_trigger%0AU-_R2-_D2&type=reconstruction"
+            )
+        );
+        assert!(parse_alg_url("https://alg.cubing.net/?alg=R_x").is_err());
+        assert!(parse_alg_url("https://alg.cubing.net/?alg=R%2").is_err());
+        assert!(parse_alg_url("https://example.com/?alg=R").is_err());
+    }
+}
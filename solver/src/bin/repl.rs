@@ -0,0 +1,363 @@
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use wasm_rubik_cube_solver::{
+    random_scramble, render_terminal, Cube, FaceletCube, Move, MoveSequence, TerminalStyle,
+};
+
+const COMMANDS: [&str; 12] = [
+    "apply", "undo", "redo", "reset", "scramble", "solve", "show", "invert", "save", "load",
+    "help", "quit",
+];
+
+const HELP: &str = "\
+apply <moves>      apply a move sequence, e.g. apply R U R' U'
+undo / redo        step backwards or forwards through the applied sequences
+reset              return to the solved cube, clearing the session
+scramble [length]  apply a random move sequence (25 moves by default)
+solve              print a solution for the current cube
+show [plain]       draw the cube's net, without colour when given plain or NO_COLOR is set
+invert [moves]     print the inverse of the given moves, or of every move applied so far
+save <path>        write the applied sequences to a file, one per line
+load <path>        replace the session with one previously saved
+quit               leave the REPL";
+
+const SCRAMBLE_LENGTH: usize = 25;
+
+/// A cube along with the sequences applied to reach it from solved; each applied sequence is a
+/// single step which can be undone and redone.
+#[derive(Default)]
+struct Session {
+    cube: FaceletCube,
+    steps: Vec<Vec<Move>>,
+    undone: Vec<Vec<Move>>,
+}
+
+impl Session {
+    fn apply(&mut self, moves: Vec<Move>) {
+        self.cube = self.cube.apply_moves(&moves);
+        self.steps.push(moves);
+        self.undone.clear();
+    }
+
+    fn undo(&mut self) -> Option<&[Move]> {
+        let step = self.steps.pop()?;
+        self.cube = self.cube.apply_moves(&invert(&step));
+        self.undone.push(step);
+        self.undone.last().map(Vec::as_slice)
+    }
+
+    fn redo(&mut self) -> Option<&[Move]> {
+        let step = self.undone.pop()?;
+        self.cube = self.cube.apply_moves(&step);
+        self.steps.push(step);
+        self.steps.last().map(Vec::as_slice)
+    }
+
+    fn moves(&self) -> Vec<Move> {
+        self.steps.iter().flatten().copied().collect()
+    }
+
+    fn save(&self) -> String {
+        self.steps
+            .iter()
+            .map(|step| format!("{}\n", MoveSequence::from(step.as_slice())))
+            .collect()
+    }
+
+    fn load(contents: &str) -> Result<Self, String> {
+        let mut session = Session::default();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            session.apply(parse_moves(line.split_whitespace())?);
+        }
+        Ok(session)
+    }
+}
+
+fn parse_moves<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<Vec<Move>, String> {
+    let sequence: MoveSequence = tokens.collect::<Vec<_>>().join(" ").parse()?;
+    Ok(sequence.0)
+}
+
+fn invert(moves: &[Move]) -> Vec<Move> {
+    moves.iter().rev().map(|action| action.inverse()).collect()
+}
+
+fn show(cube: &FaceletCube, plain: bool) -> String {
+    let style = if plain || env::var_os("NO_COLOR").is_some() {
+        TerminalStyle::Plain
+    } else {
+        TerminalStyle::Ansi
+    };
+    render_terminal(cube, style)
+}
+
+/// Runs a single command against the session, returning the output to print.
+fn execute(session: &mut Session, line: &str) -> Result<String, String> {
+    let mut tokens = line.split_whitespace();
+    let command = match tokens.next() {
+        Some(command) => command,
+        None => return Ok(String::new()),
+    };
+
+    match command {
+        "apply" => {
+            let moves = parse_moves(tokens)?;
+            if moves.is_empty() {
+                return Err("Nothing to apply".to_string());
+            }
+            session.apply(moves);
+            Ok(show(&session.cube, false))
+        }
+        "undo" => match session.undo() {
+            Some(step) => Ok(format!(
+                "Undid {}\n{}",
+                MoveSequence::from(step),
+                show(&session.cube, false)
+            )),
+            None => Err("Nothing to undo".to_string()),
+        },
+        "redo" => match session.redo() {
+            Some(step) => Ok(format!(
+                "Redid {}\n{}",
+                MoveSequence::from(step),
+                show(&session.cube, false)
+            )),
+            None => Err("Nothing to redo".to_string()),
+        },
+        "reset" => {
+            *session = Session::default();
+            Ok(show(&session.cube, false))
+        }
+        "scramble" => {
+            let length = match tokens.next() {
+                Some(length) => length
+                    .parse()
+                    .map_err(|_| "Scramble length must be a number".to_string())?,
+                None => SCRAMBLE_LENGTH,
+            };
+            let moves = random_scramble(length);
+            let output = format!("{}\n", MoveSequence::from(moves.as_slice()));
+            session.apply(moves);
+            Ok(output + &show(&session.cube, false))
+        }
+        "solve" => match session.cube.solve() {
+            Some(solution) if solution.is_empty() => Ok("Cube is already solved".to_string()),
+            Some(solution) => Ok(format!(
+                "{} ({} moves)",
+                MoveSequence::from(solution.as_slice()),
+                solution.len()
+            )),
+            None => Err("Cube has no solution".to_string()),
+        },
+        "show" => Ok(show(&session.cube, tokens.next() == Some("plain"))),
+        "invert" => {
+            let moves = parse_moves(tokens)?;
+            if moves.is_empty() {
+                Ok(MoveSequence(invert(&session.moves())).to_string())
+            } else {
+                Ok(MoveSequence(invert(&moves)).to_string())
+            }
+        }
+        "save" => {
+            let path = tokens.next().ok_or("A path to save to is required")?;
+            fs::write(path, session.save()).map_err(|err| err.to_string())?;
+            Ok(format!("Saved {} steps to {}", session.steps.len(), path))
+        }
+        "load" => {
+            let path = tokens.next().ok_or("A path to load from is required")?;
+            let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+            *session = Session::load(&contents)?;
+            Ok(show(&session.cube, false))
+        }
+        "help" => Ok(HELP.to_string()),
+        _ => Err(format!("Unknown command '{}', try help", command)),
+    }
+}
+
+/// Completes command names, move tokens for the commands which take moves, and paths for those
+/// which take files.
+struct ReplHelper {
+    filenames: FilenameCompleter,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind(' ').map_or(0, |idx| idx + 1);
+        let word = &line[start..pos];
+        let candidates = |options: Vec<String>| -> Vec<Pair> {
+            options
+                .into_iter()
+                .filter(|option| option.starts_with(word))
+                .map(|option| Pair {
+                    display: option.clone(),
+                    replacement: option,
+                })
+                .collect()
+        };
+
+        match line.split_whitespace().next() {
+            _ if start == 0 => Ok((
+                start,
+                candidates(COMMANDS.iter().map(|command| command.to_string()).collect()),
+            )),
+            Some("apply") | Some("invert") => Ok((
+                start,
+                candidates(
+                    Move::available()
+                        .iter()
+                        .map(|action| action.to_string())
+                        .collect(),
+                ),
+            )),
+            Some("save") | Some("load") => self.filenames.complete(line, pos, ctx),
+            _ => Ok((start, vec![])),
+        }
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".cube_repl_history"))
+}
+
+fn main() {
+    let mut editor = Editor::<ReplHelper>::new();
+    editor.set_helper(Some(ReplHelper {
+        filenames: FilenameCompleter::new(),
+    }));
+    if let Some(path) = history_path() {
+        let _ = editor.load_history(&path);
+    }
+
+    let mut session = Session::default();
+    println!("Type help for a list of commands.");
+
+    loop {
+        match editor.readline("cube> ") {
+            Ok(line) => {
+                editor.add_history_entry(line.as_str());
+                match line.trim() {
+                    "quit" | "exit" => break,
+                    line => match execute(&mut session, line) {
+                        Ok(output) if output.is_empty() => {}
+                        Ok(output) => println!("{}", output.trim_end()),
+                        Err(err) => eprintln!("{}", err),
+                    },
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("{}", err);
+                break;
+            }
+        }
+    }
+
+    if let Some(path) = history_path() {
+        let _ = editor.save_history(&path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_step_through_applied_sequences() {
+        let mut session = Session::default();
+        execute(&mut session, "apply R U R' U'").unwrap();
+        execute(&mut session, "apply F").unwrap();
+
+        execute(&mut session, "undo").unwrap();
+        assert_eq!(
+            FaceletCube::default().apply_moves(&parse_moves("R U R' U'".split(' ')).unwrap()),
+            session.cube
+        );
+
+        execute(&mut session, "undo").unwrap();
+        assert_eq!(FaceletCube::default(), session.cube);
+        assert!(execute(&mut session, "undo").is_err());
+
+        execute(&mut session, "redo").unwrap();
+        execute(&mut session, "redo").unwrap();
+        assert_eq!("R U R' U' F", MoveSequence(session.moves()).to_string());
+        assert!(execute(&mut session, "redo").is_err());
+    }
+
+    #[test]
+    fn applying_clears_undone_steps() {
+        let mut session = Session::default();
+        execute(&mut session, "apply R").unwrap();
+        execute(&mut session, "undo").unwrap();
+        execute(&mut session, "apply U").unwrap();
+
+        assert!(execute(&mut session, "redo").is_err());
+    }
+
+    #[test]
+    fn inverts_moves() {
+        let mut session = Session::default();
+        execute(&mut session, "apply R U2 F'").unwrap();
+
+        assert_eq!(Ok("F U2 R'".to_string()), execute(&mut session, "invert"));
+        assert_eq!(Ok("U R".to_string()), execute(&mut session, "invert R' U'"));
+    }
+
+    #[test]
+    fn solves_scrambled_cube() {
+        let mut session = Session::default();
+        execute(&mut session, "scramble 30").unwrap();
+        let solution = execute(&mut session, "solve").unwrap();
+
+        let moves = parse_moves(solution.split(" (").next().unwrap().split(' ')).unwrap();
+        assert_eq!(FaceletCube::default(), session.cube.apply_moves(&moves));
+    }
+
+    #[test]
+    fn sessions_are_saved_and_loaded() {
+        let mut session = Session::default();
+        execute(&mut session, "apply R U").unwrap();
+        execute(&mut session, "scramble 5").unwrap();
+
+        let loaded = Session::load(&session.save()).unwrap();
+
+        assert_eq!(session.cube, loaded.cube);
+        assert_eq!(session.steps, loaded.steps);
+        assert!(Session::load("R X").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_input() {
+        let mut session = Session::default();
+
+        assert!(execute(&mut session, "twist").is_err());
+        assert!(execute(&mut session, "apply R Q").is_err());
+        assert!(execute(&mut session, "apply").is_err());
+        assert!(execute(&mut session, "scramble lots").is_err());
+    }
+}
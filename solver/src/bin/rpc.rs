@@ -0,0 +1,34 @@
+use std::env;
+use std::io;
+use std::process;
+
+use wasm_rubik_cube_solver::{install_phase_tables, serve_rpc, PhaseTables};
+
+const USAGE: &str = "usage: cube-rpc [--tables <path>]";
+
+fn main() {
+    let mut args = env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        match (flag.as_str(), args.next()) {
+            ("--tables", Some(path)) => match PhaseTables::load(&path) {
+                Ok(tables) => install_phase_tables(tables),
+                Err(err) => {
+                    eprintln!("Unable to load phase tables from {}: {}", path, err);
+                    process::exit(1);
+                }
+            },
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    if let Err(err) = serve_rpc(stdin.lock(), stdout.lock()) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,48 @@
+use std::env;
+use std::process;
+use std::time::Duration;
+
+use wasm_rubik_cube_solver::{serve, ServerConfig};
+
+const USAGE: &str = "\
+usage: cube-server [--address <host:port>] [--workers <count>] [--max-solves <count>]
+                   [--solve-timeout-ms <milliseconds>]";
+
+fn parse_args() -> Result<ServerConfig, String> {
+    let mut config = ServerConfig::default();
+    let mut args = env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid value for {}", flag))
+        };
+
+        match flag.as_str() {
+            "--address" => config.address = value.clone(),
+            "--workers" => config.workers = number()? as usize,
+            "--max-solves" => config.max_solves = number()? as usize,
+            "--solve-timeout-ms" => config.solve_timeout = Duration::from_millis(number()?),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    Ok(config)
+}
+
+fn main() {
+    let config = parse_args().unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        process::exit(2);
+    });
+
+    println!("Listening on http://{}", config.address);
+    if let Err(err) = serve(&config) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
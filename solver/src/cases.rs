@@ -0,0 +1,214 @@
+//! A catalogue of last layer cases, and scrambles setting them up for drilling an algorithm.
+//!
+//! A case's state is built from its algorithm, but the scramble reaching it is found by solving
+//! that state, so it has nothing in common with the algorithm and can not be recognised by
+//! reading it backwards.
+
+use rand::Rng;
+use serde::Serialize;
+
+use crate::cube::Cube;
+use crate::cubie_cube::CubieCube;
+use crate::moves::Direction::*;
+use crate::moves::Position::*;
+use crate::moves::{Move, MoveSequence};
+use crate::pochmann_solver::solve;
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize)]
+pub struct Case {
+    /// The step the case belongs to, e.g. "PLL".
+    pub set: &'static str,
+    pub name: &'static str,
+    /// An algorithm solving the case, in face turns only.
+    pub algorithm: &'static str,
+}
+
+const fn pll(name: &'static str, algorithm: &'static str) -> Case {
+    Case {
+        set: "PLL",
+        name,
+        algorithm,
+    }
+}
+
+const CASES: [Case; 21] = [
+    pll("Aa", "R' F R' B2 R F' R' B2 R2"),
+    pll("Ab", "R2 B2 R F R' B2 R F' R"),
+    pll("E", "R B' R' F R B R' F' R B R' F R B' R' F'"),
+    pll("F", "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R"),
+    pll("Ga", "R2 U R' U R' U' R U' R2 U' D R' U R D'"),
+    pll("Gb", "R' U' R U D' R2 U R' U R U' R U' R2 D"),
+    pll("Gc", "R2 U' R U' R U R' U R2 U D' R U' R' D"),
+    pll("Gd", "R U R' U' D R2 U' R U' R' U R' U R2 D'"),
+    pll("H", "R2 U2 R U2 R2 U2 R2 U2 R U2 R2"),
+    pll("Ja", "R' U L' U2 R U' R' U2 R L"),
+    pll("Jb", "R U R' F' R U R' U' R' F R2 U' R'"),
+    pll(
+        "Na",
+        "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'",
+    ),
+    pll("Nb", "R' U R U' R' F' U' F R U R' F R' F' R U' R"),
+    pll("Ra", "R U' R' U' R U R D R' U' R D' R' U2 R'"),
+    pll("Rb", "R2 F R U R U' R' F' R U2 R' U2 R"),
+    pll("T", "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    pll("Ua", "R U' R U R U R U' R' U' R2"),
+    pll("Ub", "R2 U R U R' U' R' U' R' U R'"),
+    pll("V", "R' U R' U' B' R' B2 U' B' U B' R B R"),
+    pll("Y", "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    pll("Z", "R' U' R U' R U R U' R' U R U R2 U' R'"),
+];
+
+/// Every case in the catalogue.
+pub fn cases() -> &'static [Case] {
+    &CASES
+}
+
+/// Looks up a case by its name, optionally preceded by its set (e.g. "Ua" or "PLL Ua").
+pub fn find_case(name: &str) -> Option<&'static Case> {
+    let name = name.trim();
+    CASES.iter().find(|case| {
+        name.eq_ignore_ascii_case(case.name)
+            || name.eq_ignore_ascii_case(&format!("{} {}", case.set, case.name))
+    })
+}
+
+impl Case {
+    pub fn algorithm(&self) -> Vec<Move> {
+        let algorithm: MoveSequence = self
+            .algorithm
+            .parse()
+            .expect("Catalogue algorithms are valid");
+        algorithm.0
+    }
+
+    /// The state the algorithm solves.
+    pub fn cube(&self) -> CubieCube {
+        let undo: Vec<Move> = self
+            .algorithm()
+            .iter()
+            .rev()
+            .map(|action| action.inverse())
+            .collect();
+        CubieCube::default().apply_moves(&undo)
+    }
+}
+
+/// How a drilled case may be presented.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Default)]
+pub struct CaseOptions {
+    /// Turns the U layer at random, so the algorithm must be preceded and followed by an AUF.
+    pub random_auf: bool,
+    /// Shows the case from a random side. Only the last layer is disturbed, so this is the same
+    /// as turning the U layer before setting the case up and undoing the turn afterwards.
+    pub random_rotation: bool,
+}
+
+fn u_turns(turns: u8) -> Vec<Move> {
+    match turns % 4 {
+        1 => vec![Move(Up, Normal)],
+        2 => vec![Move(Up, Half)],
+        3 => vec![Move(Up, Prime)],
+        _ => vec![],
+    }
+}
+
+/// The case as it will be presented, turned according to the options.
+pub fn case_cube(case: &Case, options: CaseOptions) -> CubieCube {
+    let mut rng = rand::thread_rng();
+    let (mut before, mut after) = (0, 0);
+    if options.random_auf {
+        before = rng.gen_range(0..4);
+        after = rng.gen_range(0..4);
+    }
+    if options.random_rotation {
+        let rotation = rng.gen_range(0..4);
+        before += rotation;
+        after += 4 - rotation;
+    }
+
+    CubieCube::default()
+        .apply_moves(&u_turns(before))
+        .multiply(case.cube())
+        .apply_moves(&u_turns(after))
+}
+
+/// A scramble setting up the case, found by solving its state rather than reversing the
+/// algorithm.
+pub fn case_scramble(case: &Case, options: CaseOptions) -> Vec<Move> {
+    solve(&case_cube(case, options))
+        .expect("Catalogue cases are solvable")
+        .iter()
+        .rev()
+        .map(|action| action.inverse())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// The same for every way the case can be presented.
+    fn recognised(cube: CubieCube) -> String {
+        (0..4)
+            .flat_map(|before| (0..4).map(move |after| (before, after)))
+            .map(|(before, after)| {
+                let turned = CubieCube::default()
+                    .apply_moves(&u_turns(before))
+                    .multiply(cube)
+                    .apply_moves(&u_turns(after));
+                format!("{:?}", turned)
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn catalogue_covers_every_pll_once() {
+        let mut seen = HashSet::new();
+        for case in cases() {
+            let cube = case.cube();
+
+            assert_ne!(CubieCube::default(), cube, "{}", case.name);
+            assert!(cube.co.iter().chain(cube.eo.iter()).all(|&o| o == 0));
+            assert!((4..8).all(|corner| cube.cp[corner] as usize == corner));
+            assert!((4..12).all(|edge| cube.ep[edge] as usize == edge));
+            assert!(seen.insert(recognised(cube)), "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn finds_cases_by_name() {
+        assert_eq!(Some("Ua"), find_case("PLL Ua").map(|case| case.name));
+        assert_eq!(Some("Gc"), find_case("gc").map(|case| case.name));
+        assert_eq!(None, find_case("Sune"));
+    }
+
+    #[test]
+    fn scrambles_set_up_the_case_without_reversing_the_algorithm() {
+        let case = find_case("Ua").unwrap();
+        let options = CaseOptions {
+            random_auf: true,
+            random_rotation: true,
+        };
+        for _ in 0..10 {
+            let scramble = case_scramble(case, options);
+            let reversed: Vec<Move> = case
+                .algorithm()
+                .iter()
+                .rev()
+                .map(|action| action.inverse())
+                .collect();
+            let cube = CubieCube::default().apply_moves(&scramble);
+
+            assert_eq!(recognised(case.cube()), recognised(cube));
+            assert!(!scramble
+                .windows(reversed.len())
+                .any(|moves| moves == reversed));
+        }
+        assert_eq!(
+            case.cube(),
+            CubieCube::default().apply_moves(&case_scramble(case, CaseOptions::default()))
+        );
+    }
+}
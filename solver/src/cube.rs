@@ -2,10 +2,10 @@ use rand::prelude::SliceRandom;
 
 use crate::moves::Move;
 
-pub(crate) trait Cube: Sized + Default {
+pub trait Cube: Sized + Default {
     fn apply_move(self, action: &Move) -> Self;
 
-    fn apply_moves(self, actions: &Vec<Move>) -> Self {
+    fn apply_moves(self, actions: &[Move]) -> Self {
         actions
             .iter()
             .fold(self, |cube, action| cube.apply_move(action))
@@ -14,7 +14,7 @@ pub(crate) trait Cube: Sized + Default {
     fn random(total_moves: u8) -> Self {
         let mut rng = rand::thread_rng();
         (0..total_moves).fold(Self::default(), |cube, _| {
-            cube.apply_move(&Move::available().choose(&mut rng).unwrap())
+            cube.apply_move(Move::available().choose(&mut rng).unwrap())
         })
     }
 }
@@ -5,6 +5,7 @@ use std::fmt;
 use std::str::FromStr;
 
 use crate::cube::Cube;
+use crate::moves::Position::{Back, Down, Front, Left, Right, Up};
 use crate::moves::{Direction, Move, Position};
 use Corner::*;
 use Edge::*;
@@ -13,6 +14,7 @@ use Edge::*;
 
 #[derive(Serialize_repr, Deserialize_repr, Debug, Copy, Clone, Hash, Eq, PartialEq)]
 #[repr(u8)]
+#[allow(clippy::upper_case_acronyms)]
 pub(crate) enum Corner {
     URF = 0,
     UFL = 1,
@@ -26,6 +28,7 @@ pub(crate) enum Corner {
 
 #[derive(Serialize_repr, Deserialize_repr, Debug, Copy, Clone, Hash, Eq, PartialEq)]
 #[repr(u8)]
+#[allow(clippy::upper_case_acronyms)]
 pub(crate) enum Edge {
     UR = 0,
     UF = 1,
@@ -41,8 +44,8 @@ pub(crate) enum Edge {
     BR = 11,
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash, Eq, PartialEq)]
-pub(crate) struct CubieCube {
+#[derive(Serialize, Deserialize, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct CubieCube {
     pub(crate) cp: [Corner; 8],
     pub(crate) co: [u8; 8],
     pub(crate) ep: [Edge; 12],
@@ -71,11 +74,11 @@ impl Cube for CubieCube {
                 Position::Front => *MOVE_F,
                 Position::Back => *MOVE_B,
             }),
-            Move(position, Direction::Half) => self.apply_moves(&vec![
+            Move(position, Direction::Half) => self.apply_moves(&[
                 Move(position, Direction::Normal),
                 Move(position, Direction::Normal),
             ]),
-            Move(position, Direction::Prime) => self.apply_moves(&vec![
+            Move(position, Direction::Prime) => self.apply_moves(&[
                 Move(position, Direction::Normal),
                 Move(position, Direction::Normal),
                 Move(position, Direction::Normal),
@@ -85,7 +88,8 @@ impl Cube for CubieCube {
 }
 
 impl CubieCube {
-    fn multiply(&self, other: Self) -> Self {
+    /// Applies `other`'s permutation and orientation changes to the cube.
+    pub(crate) fn multiply(&self, other: Self) -> Self {
         let mut new_ep = self.ep;
         let mut new_eo = self.eo;
 
@@ -141,12 +145,151 @@ impl CubieCube {
     }
 }
 
+impl CubieCube {
+    /// Checks the cube is reachable from solved; every piece appears once, the corner twists and
+    /// edge flips cancel out, and the corner and edge permutations have the same parity.
+    pub fn verify(&self) -> Result<(), &'static str> {
+        if (0..8).any(|corner| !self.cp.iter().any(|&c| c as usize == corner)) {
+            return Err("Each corner must appear exactly once");
+        }
+        if (0..12).any(|edge| !self.ep.iter().any(|&e| e as usize == edge)) {
+            return Err("Each edge must appear exactly once");
+        }
+        if self.co.iter().any(|&co| co > 2) || self.co.iter().sum::<u8>() % 3 != 0 {
+            return Err("Corner twist is invalid");
+        }
+        if self.eo.iter().any(|&eo| eo > 1) || self.eo.iter().sum::<u8>() % 2 != 0 {
+            return Err("Edge flip is invalid");
+        }
+        if parity(&self.cp.iter().map(|&c| c as u8).collect::<Vec<_>>())
+            != parity(&self.ep.iter().map(|&e| e as u8).collect::<Vec<_>>())
+        {
+            return Err("Corner and edge permutation parities differ");
+        }
+        Ok(())
+    }
+}
+
+impl CubieCube {
+    /// The cube which undoes this one, such that multiplying the two gives the solved cube.
+    pub(crate) fn inverse(&self) -> Self {
+        let mut inverse = *self;
+
+        for (idx, (&corner, &co)) in self.cp.iter().zip(self.co.iter()).enumerate() {
+            inverse.cp[corner as usize] = CubieCube::default().cp[idx];
+            inverse.co[corner as usize] = (3 - co) % 3;
+        }
+        for (idx, (&edge, &eo)) in self.ep.iter().zip(self.eo.iter()).enumerate() {
+            inverse.ep[edge as usize] = CubieCube::default().ep[idx];
+            inverse.eo[edge as usize] = eo;
+        }
+
+        inverse
+    }
+}
+
+/// The faces each corner position touches.
+pub(crate) static CORNER_FACES: [[Position; 3]; 8] = [
+    [Up, Right, Front],
+    [Up, Front, Left],
+    [Up, Left, Back],
+    [Up, Back, Right],
+    [Down, Front, Right],
+    [Down, Left, Front],
+    [Down, Back, Left],
+    [Down, Right, Back],
+];
+
+/// The faces each edge position touches.
+pub(crate) static EDGE_FACES: [[Position; 2]; 12] = [
+    [Up, Right],
+    [Up, Front],
+    [Up, Left],
+    [Up, Back],
+    [Down, Right],
+    [Down, Front],
+    [Down, Left],
+    [Down, Back],
+    [Front, Right],
+    [Front, Left],
+    [Back, Left],
+    [Back, Right],
+];
+
+impl CubieCube {
+    /// Whether the corner position holds its own corner, untwisted.
+    pub(crate) fn corner_solved(&self, corner: usize) -> bool {
+        self.cp[corner] as usize == corner && self.co[corner] == 0
+    }
+
+    /// Whether the edge position holds its own edge, unflipped.
+    pub(crate) fn edge_solved(&self, edge: usize) -> bool {
+        self.ep[edge] as usize == edge && self.eo[edge] == 0
+    }
+
+    /// Whether every edge on the face is solved.
+    pub(crate) fn cross_solved(&self, face: Position) -> bool {
+        (0..12)
+            .filter(|&edge| EDGE_FACES[edge].contains(&face))
+            .all(|edge| self.edge_solved(edge))
+    }
+}
+
+/// Whether the permutation is odd.
+pub(crate) fn parity(values: &[u8]) -> bool {
+    let inversions: usize = (0..values.len())
+        .map(|i| values[i + 1..].iter().filter(|&&v| v < values[i]).count())
+        .sum();
+    inversions % 2 == 1
+}
+
 impl fmt::Display for CubieCube {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", serde_json::to_string(self).unwrap())
     }
 }
 
+/// A piece along with its orientation, debug formatted as e.g. `DFR+2`.
+struct Oriented<'a, P>(&'a P, u8);
+
+impl<P: fmt::Debug> fmt::Debug for Oriented<'_, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}+{}", self.0, self.1)
+    }
+}
+
+/// The pieces held in each position, as listed by `CubieCube`'s `Debug` output.
+struct Pieces<'a, P>(&'a [P], &'a [P], &'a [u8]);
+
+impl<P: fmt::Debug> fmt::Debug for Pieces<'_, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Pieces(positions, pieces, orientations) = self;
+        f.debug_map()
+            .entries(
+                positions.iter().zip(
+                    pieces
+                        .iter()
+                        .zip(orientations.iter())
+                        .map(|(piece, &ori)| Oriented(piece, ori)),
+                ),
+            )
+            .finish()
+    }
+}
+
+/// Lists which piece sits in each position along with its orientation (e.g. `URF: DFR+2` for
+/// the DFR corner twisted twice in the URF position); use `{:#?}` for one position per line.
+impl fmt::Debug for CubieCube {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let solved = CubieCube::default();
+
+        f.debug_struct("CubieCube")
+            .field("corners", &Pieces(&solved.cp, &self.cp, &self.co))
+            .field("edges", &Pieces(&solved.ep, &self.ep, &self.eo))
+            .finish()
+    }
+}
+
 impl FromStr for CubieCube {
     type Err = &'static str;
 
@@ -239,7 +382,7 @@ mod tests {
     fn applying_half_move_twice_returns_to_initial_state() {
         assert_eq!(
             CubieCube::default(),
-            CubieCube::default().apply_moves(&vec![
+            CubieCube::default().apply_moves(&[
                 Move(Position::Front, Direction::Half),
                 Move(Position::Front, Direction::Half)
             ])
@@ -250,7 +393,7 @@ mod tests {
     fn applying_double_quarter_turn_is_the_same_as_single_half_turn() {
         assert_eq!(
             CubieCube::default().apply_move(&Move(Position::Front, Direction::Half)),
-            CubieCube::default().apply_moves(&vec![
+            CubieCube::default().apply_moves(&[
                 Move(Position::Front, Direction::Normal),
                 Move(Position::Front, Direction::Normal)
             ])
@@ -258,10 +401,68 @@ mod tests {
 
         assert_eq!(
             CubieCube::default().apply_move(&Move(Position::Front, Direction::Half)),
-            CubieCube::default().apply_moves(&vec![
+            CubieCube::default().apply_moves(&[
                 Move(Position::Front, Direction::Prime),
                 Move(Position::Front, Direction::Prime)
             ])
         );
     }
+
+    #[test]
+    fn debug_lists_pieces_and_orientations() {
+        let cube = CubieCube::default().apply_move(&Move(Position::Front, Direction::Normal));
+        let debug = format!("{:?}", cube);
+
+        assert!(debug.starts_with("CubieCube { corners: {URF: UFL+1, UFL: DLF+2, ULB: ULB+0,"));
+        assert!(debug.contains("edges: {UR: UR+0, UF: FL+1,"));
+        assert_eq!(
+            20,
+            format!("{:#?}", cube)
+                .lines()
+                .filter(|line| line.contains('+'))
+                .count()
+        );
+    }
+
+    #[test]
+    fn reachable_cubes_are_verified() {
+        assert_eq!(Ok(()), CubieCube::random(50).verify());
+    }
+
+    #[test]
+    fn unreachable_cubes_are_rejected() {
+        let mut twisted = CubieCube::default();
+        twisted.co[0] = 1;
+        let mut flipped = CubieCube::default();
+        flipped.eo[0] = 1;
+        let mut swapped = CubieCube::default();
+        swapped.ep.swap(0, 1);
+        let mut duplicated = CubieCube::default();
+        duplicated.cp[0] = UFL;
+
+        assert_eq!(Err("Corner twist is invalid"), twisted.verify());
+        assert_eq!(Err("Edge flip is invalid"), flipped.verify());
+        assert_eq!(
+            Err("Corner and edge permutation parities differ"),
+            swapped.verify()
+        );
+        assert_eq!(
+            Err("Each corner must appear exactly once"),
+            duplicated.verify()
+        );
+    }
+
+    #[test]
+    fn multiplying_by_inverse_gives_solved_cube() {
+        let cube = CubieCube::random(50);
+
+        assert_eq!(CubieCube::default(), cube.multiply(cube.inverse()));
+        assert_eq!(CubieCube::default(), cube.inverse().multiply(cube));
+        assert_eq!(
+            CubieCube::default().apply_move(&Move(Position::Right, Direction::Prime)),
+            CubieCube::default()
+                .apply_move(&Move(Position::Right, Direction::Normal))
+                .inverse()
+        );
+    }
 }
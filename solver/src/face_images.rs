@@ -0,0 +1,364 @@
+//! Reads a cube from photographs of its six faces.
+//!
+//! The stickers of a face are found as the bright, roughly square blobs separated by the cube's
+//! dark plastic: pixels are split into light and dark by Otsu's threshold, the light ones grouped
+//! into connected blobs, and the nine similarly sized blobs nearest the middle of the photo are
+//! taken to be the face's 3x3 grid. Each sticker's colour is the median of the middle of its blob,
+//! keeping glare and shadows at its edges out of the sample.
+
+use crate::sticker_colours::{classify_stickers, ColourClassification, StickerSample};
+
+/// Photos larger than this (in either dimension) are searched at a reduced resolution.
+const SEARCH_SIZE: usize = 200;
+
+/// A photo of a single face, as packed 8-bit RGB or RGBA pixels (e.g. a canvas' `ImageData`).
+#[derive(Debug, Copy, Clone)]
+pub struct FaceImage<'a> {
+    width: usize,
+    height: usize,
+    channels: usize,
+    data: &'a [u8],
+}
+
+impl<'a> FaceImage<'a> {
+    pub fn rgb(width: usize, height: usize, data: &'a [u8]) -> Result<Self, &'static str> {
+        FaceImage::new(width, height, 3, data)
+    }
+
+    pub fn rgba(width: usize, height: usize, data: &'a [u8]) -> Result<Self, &'static str> {
+        FaceImage::new(width, height, 4, data)
+    }
+
+    fn new(
+        width: usize,
+        height: usize,
+        channels: usize,
+        data: &'a [u8],
+    ) -> Result<Self, &'static str> {
+        if width == 0 || height == 0 || data.len() != width * height * channels {
+            return Err("Image data does not match its dimensions");
+        }
+        Ok(FaceImage {
+            width,
+            height,
+            channels,
+            data,
+        })
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        let idx = (y * self.width + x) * self.channels;
+        [self.data[idx], self.data[idx + 1], self.data[idx + 2]]
+    }
+}
+
+/// The stickers found in a photo of a face, in reading order (left to right, top to bottom).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaceGrid {
+    /// The centre of each sticker, in pixels.
+    pub centres: [(f32, f32); 9],
+    pub samples: [StickerSample; 9],
+}
+
+/// A connected group of light pixels, in search coordinates.
+#[derive(Debug, Clone)]
+struct Blob {
+    area: usize,
+    min: (usize, usize),
+    max: (usize, usize),
+    sum: (usize, usize),
+}
+
+impl Blob {
+    fn centre(&self) -> (f32, f32) {
+        (
+            self.sum.0 as f32 / self.area as f32,
+            self.sum.1 as f32 / self.area as f32,
+        )
+    }
+
+    fn size(&self) -> (usize, usize) {
+        (self.max.0 - self.min.0 + 1, self.max.1 - self.min.1 + 1)
+    }
+
+    /// Stickers fill most of their bounding box and are not much wider than they are tall.
+    fn is_sticker_shaped(&self) -> bool {
+        let (width, height) = self.size();
+        self.area * 2 >= width * height && width <= 2 * height && height <= 2 * width
+    }
+}
+
+/// The brightness which best separates the histogram's dark and light pixels.
+fn otsu_threshold(histogram: &[usize; 256]) -> u8 {
+    let total: usize = histogram.iter().sum();
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(value, &count)| (value * count) as f64)
+        .sum();
+
+    let (mut best, mut best_variance) = (0, 0.0);
+    let (mut dark_count, mut dark_sum) = (0, 0.0);
+    for (value, &count) in histogram.iter().enumerate() {
+        dark_count += count;
+        dark_sum += (value * count) as f64;
+        let light_count = total - dark_count;
+        if dark_count == 0 || light_count == 0 {
+            continue;
+        }
+        let dark_mean = dark_sum / dark_count as f64;
+        let light_mean = (sum - dark_sum) / light_count as f64;
+        let variance = dark_count as f64 * light_count as f64 * (dark_mean - light_mean).powi(2);
+        if variance > best_variance {
+            best = value;
+            best_variance = variance;
+        }
+    }
+    best as u8
+}
+
+/// Finds the connected light regions of the mask, ignoring those touching its border (which are
+/// the background rather than the cube).
+fn blobs(mask: &[bool], width: usize, height: usize) -> Vec<Blob> {
+    let mut seen = vec![false; mask.len()];
+    let mut found = vec![];
+
+    for start in 0..mask.len() {
+        if !mask[start] || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let (x, y) = (start % width, start / width);
+        let mut blob = Blob {
+            area: 0,
+            min: (x, y),
+            max: (x, y),
+            sum: (0, 0),
+        };
+        let mut touches_border = false;
+
+        while let Some(idx) = stack.pop() {
+            let (x, y) = (idx % width, idx / width);
+            blob.area += 1;
+            blob.sum = (blob.sum.0 + x, blob.sum.1 + y);
+            blob.min = (blob.min.0.min(x), blob.min.1.min(y));
+            blob.max = (blob.max.0.max(x), blob.max.1.max(y));
+            touches_border |= x == 0 || y == 0 || x == width - 1 || y == height - 1;
+
+            let neighbours = [
+                (x > 0).then(|| idx - 1),
+                (x + 1 < width).then(|| idx + 1),
+                (y > 0).then(|| idx - width),
+                (y + 1 < height).then(|| idx + width),
+            ];
+            for next in neighbours.iter().flatten() {
+                if mask[*next] && !seen[*next] {
+                    seen[*next] = true;
+                    stack.push(*next);
+                }
+            }
+        }
+
+        if !touches_border {
+            found.push(blob);
+        }
+    }
+    found
+}
+
+/// Picks nine similarly sized stickers nearest the middle of the photo, in reading order.
+fn grid(candidates: &[Blob], middle: (f32, f32)) -> Option<Vec<Blob>> {
+    let distance = |blob: &Blob| {
+        let (x, y) = blob.centre();
+        (x - middle.0).powi(2) + (y - middle.1).powi(2)
+    };
+
+    let mut stickers = candidates
+        .iter()
+        .map(|reference| {
+            let mut similar: Vec<&Blob> = candidates
+                .iter()
+                .filter(|blob| blob.area * 2 >= reference.area && blob.area <= reference.area * 2)
+                .collect();
+            similar.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+            similar
+        })
+        .filter(|similar| similar.len() >= 9)
+        .max_by_key(|similar| similar.len())?
+        .into_iter()
+        .take(9)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    stickers.sort_by(|a, b| a.centre().1.total_cmp(&b.centre().1));
+    for row in stickers.chunks_mut(3) {
+        row.sort_by(|a, b| a.centre().0.total_cmp(&b.centre().0));
+    }
+
+    // Each row should be evenly spaced, with the rows themselves one sticker apart.
+    let spacing = |a: &Blob, b: &Blob| {
+        let ((ax, ay), (bx, by)) = (a.centre(), b.centre());
+        ((ax - bx).powi(2) + (ay - by).powi(2)).sqrt()
+    };
+    let pitch = spacing(&stickers[3], &stickers[4]);
+    let gaps = [
+        spacing(&stickers[0], &stickers[1]),
+        spacing(&stickers[1], &stickers[2]),
+        spacing(&stickers[4], &stickers[5]),
+        spacing(&stickers[6], &stickers[7]),
+        spacing(&stickers[7], &stickers[8]),
+        spacing(&stickers[1], &stickers[4]),
+        spacing(&stickers[4], &stickers[7]),
+    ];
+    if gaps
+        .iter()
+        .all(|&gap| gap > pitch * 0.7 && gap < pitch * 1.3)
+    {
+        Some(stickers)
+    } else {
+        None
+    }
+}
+
+/// The per-channel median of the middle half of the sticker, in photo coordinates.
+fn sample(image: &FaceImage, min: (usize, usize), max: (usize, usize)) -> StickerSample {
+    let (width, height) = (max.0 - min.0, max.1 - min.1);
+    let mut channels: [Vec<u8>; 3] = [vec![], vec![], vec![]];
+    for y in min.1 + height / 4..=max.1 - height / 4 {
+        for x in min.0 + width / 4..=max.0 - width / 4 {
+            for (channel, &value) in channels.iter_mut().zip(image.pixel(x, y).iter()) {
+                channel.push(value);
+            }
+        }
+    }
+    let [r, g, b] = channels.map(|mut values| {
+        values.sort_unstable();
+        values[values.len() / 2]
+    });
+    StickerSample::Rgb(r, g, b)
+}
+
+/// Locates the face's 3x3 sticker grid in the photo and samples each sticker's colour.
+pub fn locate_face_grid(image: &FaceImage) -> Result<FaceGrid, &'static str> {
+    let step = image.width.max(image.height).div_ceil(SEARCH_SIZE).max(1);
+    let (width, height) = (image.width / step, image.height / step);
+
+    let brightness: Vec<u8> = (0..width * height)
+        .map(|idx| {
+            let [r, g, b] = image.pixel(idx % width * step, idx / width * step);
+            r.max(g).max(b)
+        })
+        .collect();
+    let mut histogram = [0; 256];
+    for &value in brightness.iter() {
+        histogram[value as usize] += 1;
+    }
+    let threshold = otsu_threshold(&histogram);
+    let mask: Vec<bool> = brightness.iter().map(|&value| value > threshold).collect();
+
+    let min_area = (width * height / 1000).max(4);
+    let candidates: Vec<Blob> = blobs(&mask, width, height)
+        .into_iter()
+        .filter(|blob| blob.area >= min_area && blob.is_sticker_shaped())
+        .collect();
+    let stickers = grid(&candidates, (width as f32 / 2.0, height as f32 / 2.0))
+        .ok_or("Could not find the face's sticker grid")?;
+
+    let mut grid = FaceGrid {
+        centres: [(0.0, 0.0); 9],
+        samples: [StickerSample::Rgb(0, 0, 0); 9],
+    };
+    for (idx, sticker) in stickers.iter().enumerate() {
+        let (x, y) = sticker.centre();
+        grid.centres[idx] = ((x + 0.5) * step as f32, (y + 0.5) * step as f32);
+        grid.samples[idx] = sample(
+            image,
+            (sticker.min.0 * step, sticker.min.1 * step),
+            (
+                (sticker.max.0 * step + step - 1).min(image.width - 1),
+                (sticker.max.1 * step + step - 1).min(image.height - 1),
+            ),
+        );
+    }
+    Ok(grid)
+}
+
+/// Reads the cube from photos of its faces, given in URFDLB order with each face held as it
+/// appears on the cube's net: U with B above it, D with F above it, and the others with U above
+/// them.
+pub fn scan_cube(faces: &[FaceImage]) -> Result<ColourClassification, &'static str> {
+    if faces.len() != 6 {
+        return Err("A photo must be given for each of the six faces");
+    }
+    let mut samples = vec![];
+    for face in faces.iter() {
+        samples.extend_from_slice(&locate_face_grid(face)?.samples);
+    }
+    classify_stickers(&samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::facelet_cube::FaceletCube;
+    use crate::moves::MoveSequence;
+
+    // Rendered photos of the faces of the cube scrambled by `SCRAMBLE`, each slightly rotated and
+    // off-centre on a textured background, with uneven lighting and sensor noise.
+    static PHOTOS: [&[u8]; 6] = [
+        include_bytes!("../tests/images/face_u.png"),
+        include_bytes!("../tests/images/face_r.png"),
+        include_bytes!("../tests/images/face_f.png"),
+        include_bytes!("../tests/images/face_d.png"),
+        include_bytes!("../tests/images/face_l.png"),
+        include_bytes!("../tests/images/face_b.png"),
+    ];
+    static SCRAMBLE: &str = "R U F' L2 D B' R2 U' F";
+
+    fn decode(png: &[u8]) -> (usize, usize, Vec<u8>) {
+        let mut reader = png::Decoder::new(png).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        data.truncate(info.buffer_size());
+        assert_eq!(png::ColorType::Rgb, info.color_type);
+        (info.width as usize, info.height as usize, data)
+    }
+
+    #[test]
+    fn locates_the_sticker_grid() {
+        let (width, height, data) = decode(PHOTOS[2]);
+        let grid = locate_face_grid(&FaceImage::rgb(width, height, &data).unwrap()).unwrap();
+
+        let (x, y) = grid.centres[4];
+        assert!((x - width as f32 / 2.0).abs() < width as f32 / 5.0);
+        assert!((y - height as f32 / 2.0).abs() < height as f32 / 5.0);
+        assert!(grid.centres[0].0 < grid.centres[2].0);
+        assert!(grid.centres[0].1 < grid.centres[6].1);
+    }
+
+    #[test]
+    fn scans_the_cube_from_six_photos() {
+        let photos: Vec<_> = PHOTOS.iter().map(|png| decode(png)).collect();
+        let faces: Vec<FaceImage> = photos
+            .iter()
+            .map(|(width, height, data)| FaceImage::rgb(*width, *height, data).unwrap())
+            .collect();
+        let moves = SCRAMBLE.parse::<MoveSequence>().unwrap().0;
+
+        assert_eq!(
+            FaceletCube::default().apply_moves(&moves),
+            scan_cube(&faces).unwrap().cube
+        );
+        assert!(scan_cube(&faces[..5]).is_err());
+    }
+
+    #[test]
+    fn rejects_photos_without_a_face() {
+        let blank = vec![90; 60 * 40 * 3];
+
+        assert!(locate_face_grid(&FaceImage::rgb(60, 40, &blank).unwrap()).is_err());
+        assert!(FaceImage::rgba(60, 40, &blank).is_err());
+    }
+}
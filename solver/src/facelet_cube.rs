@@ -1,10 +1,12 @@
-use std::convert::TryInto;
+use serde::{Serialize, Serializer};
 use std::fmt;
 use std::str::FromStr;
 
 use crate::cube::Cube;
-use crate::cubie_cube::CubieCube;
+use crate::cubie_cube::{Corner, CubieCube, Edge};
+use crate::facelet_format::FaceletFormat;
 use crate::moves::Move;
+use crate::pochmann_solver::solve;
 use Colour::*;
 use Facelet::*;
 
@@ -12,7 +14,7 @@ use Facelet::*;
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
 #[repr(u8)]
-enum Colour {
+pub(crate) enum Colour {
     U = 0,
     R = 1,
     F = 2,
@@ -23,7 +25,7 @@ enum Colour {
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
 #[allow(dead_code)]
-enum Facelet {
+pub(crate) enum Facelet {
     U1 = 0,
     U2 = 1,
     U3 = 2,
@@ -80,38 +82,9 @@ enum Facelet {
     B9 = 53,
 }
 
-impl fmt::Display for Colour {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Self::U => write!(f, "W"),
-            Self::R => write!(f, "R"),
-            Self::F => write!(f, "G"),
-            Self::D => write!(f, "Y"),
-            Self::L => write!(f, "O"),
-            Self::B => write!(f, "B"),
-        }
-    }
-}
-
-impl FromStr for Colour {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "W" => Ok(Self::U),
-            "R" => Ok(Self::R),
-            "G" => Ok(Self::F),
-            "Y" => Ok(Self::D),
-            "O" => Ok(Self::L),
-            "B" => Ok(Self::B),
-            _ => Err("Unknown facelet colour"),
-        }
-    }
-}
-
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
-pub(crate) struct FaceletCube {
-    state: [Colour; 54],
+pub struct FaceletCube {
+    pub(crate) state: [Colour; 54],
 }
 
 impl Cube for FaceletCube {
@@ -120,6 +93,30 @@ impl Cube for FaceletCube {
     }
 }
 
+impl FaceletCube {
+    /// Solves the cube using Pochmann's Thistlethwaite-based solver, returning `None` when the
+    /// cube is unsolvable.
+    pub fn solve(&self) -> Option<Vec<Move>> {
+        solve(&CubieCube::from(*self))
+    }
+
+    /// Checks the facelets describe a cube which can be solved; each colour covers nine
+    /// facelets, the stickers form real pieces, and those pieces are arranged reachably.
+    pub fn verify(&self) -> Result<(), &'static str> {
+        for colour in [U, R, F, D, L, B].iter() {
+            if self.state.iter().filter(|&c| c == colour).count() != 9 {
+                return Err("Each colour must appear on nine facelets");
+            }
+        }
+
+        let cube = CubieCube::from(*self);
+        if FaceletCube::from(cube) != *self {
+            return Err("Facelets do not form valid pieces");
+        }
+        cube.verify()
+    }
+}
+
 impl Default for FaceletCube {
     #[rustfmt::skip]
     fn default() -> Self {
@@ -138,14 +135,13 @@ impl Default for FaceletCube {
 
 impl fmt::Display for FaceletCube {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.state
-                .iter()
-                .map(|face| format!("{}", face))
-                .collect::<String>()
-        )
+        write!(f, "{}", FaceletFormat::default().format(self))
+    }
+}
+
+impl Serialize for FaceletCube {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
     }
 }
 
@@ -153,19 +149,27 @@ impl FromStr for FaceletCube {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let facelets: Vec<Colour> = s
-            .chars()
-            .map(|facelet| facelet.to_string().parse())
-            .collect::<Result<Vec<_>, _>>()?;
-
-        match facelets.try_into() {
-            Ok(state) => Ok(FaceletCube { state }),
-            Err(_) => Err("Invalid facelet cube representation"),
-        }
+        FaceletFormat::default().parse(s)
     }
 }
 
-static CORNER_FACELETS: [[Facelet; 3]; 8] = [
+/// A point or direction, with x to the right, y up and z to the front.
+pub(crate) type Vector = [i8; 3];
+
+/// The cubie a facelet is on and the direction it faces.
+pub(crate) fn facelet_location(idx: usize) -> (Vector, Vector) {
+    let (row, col) = ((idx % 9 / 3) as i8, (idx % 3) as i8);
+    match idx / 9 {
+        0 => ([col - 1, 1, row - 1], [0, 1, 0]),
+        1 => ([1, 1 - row, 1 - col], [1, 0, 0]),
+        2 => ([col - 1, 1 - row, 1], [0, 0, 1]),
+        3 => ([col - 1, -1, 1 - row], [0, -1, 0]),
+        4 => ([-1, 1 - row, col - 1], [-1, 0, 0]),
+        _ => ([1 - col, 1 - row, -1], [0, 0, -1]),
+    }
+}
+
+pub(crate) static CORNER_FACELETS: [[Facelet; 3]; 8] = [
     [U9, R1, F3],
     [U7, F1, L3],
     [U1, L1, B3],
@@ -176,7 +180,7 @@ static CORNER_FACELETS: [[Facelet; 3]; 8] = [
     [D9, R9, B7],
 ];
 
-static EDGE_FACELETS: [[Facelet; 2]; 12] = [
+pub(crate) static EDGE_FACELETS: [[Facelet; 2]; 12] = [
     [U6, R2],
     [U8, F2],
     [U4, L2],
@@ -191,7 +195,7 @@ static EDGE_FACELETS: [[Facelet; 2]; 12] = [
     [B4, R6],
 ];
 
-static CORNER_COLOURS: [[Colour; 3]; 8] = [
+pub(crate) static CORNER_COLOURS: [[Colour; 3]; 8] = [
     [U, R, F],
     [U, F, L],
     [U, L, B],
@@ -202,7 +206,7 @@ static CORNER_COLOURS: [[Colour; 3]; 8] = [
     [D, R, B],
 ];
 
-static EDGE_COLOURS: [[Colour; 2]; 12] = [
+pub(crate) static EDGE_COLOURS: [[Colour; 2]; 12] = [
     [U, R],
     [U, F],
     [U, L],
@@ -240,6 +244,7 @@ impl From<CubieCube> for FaceletCube {
 }
 
 impl From<FaceletCube> for CubieCube {
+    #[allow(clippy::needless_range_loop)]
     fn from(faces: FaceletCube) -> Self {
         let mut cube = CubieCube::default();
 
@@ -262,7 +267,7 @@ impl From<FaceletCube> for CubieCube {
             for j in 0..cube.cp.len() {
                 let col = CORNER_COLOURS[j];
                 if col1 == col[1] && col2 == col[2] {
-                    cube.cp[i] = unsafe { std::mem::transmute(j as u8) };
+                    cube.cp[i] = unsafe { std::mem::transmute::<u8, Corner>(j as u8) };
                     cube.co[i] = ori as u8;
                     break;
                 }
@@ -274,7 +279,7 @@ impl From<FaceletCube> for CubieCube {
                 if faces.state[EDGE_FACELETS[i][0] as usize] == EDGE_COLOURS[j][0]
                     && faces.state[EDGE_FACELETS[i][1] as usize] == EDGE_COLOURS[j][1]
                 {
-                    cube.ep[i] = unsafe { std::mem::transmute(j as u8) };
+                    cube.ep[i] = unsafe { std::mem::transmute::<u8, Edge>(j as u8) };
                     cube.eo[i] = 0;
                     break;
                 }
@@ -282,7 +287,7 @@ impl From<FaceletCube> for CubieCube {
                 if faces.state[EDGE_FACELETS[i][0] as usize] == EDGE_COLOURS[j][1]
                     && faces.state[EDGE_FACELETS[i][1] as usize] == EDGE_COLOURS[j][0]
                 {
-                    cube.ep[i] = unsafe { std::mem::transmute(j as u8) };
+                    cube.ep[i] = unsafe { std::mem::transmute::<u8, Edge>(j as u8) };
                     cube.eo[i] = 1;
                     break;
                 }
@@ -335,7 +340,7 @@ mod tests {
     fn applying_half_move_twice_returns_to_initial_state() {
         assert_eq!(
             FaceletCube::default(),
-            FaceletCube::default().apply_moves(&vec![
+            FaceletCube::default().apply_moves(&[
                 Move(Position::Front, Direction::Half),
                 Move(Position::Front, Direction::Half)
             ])
@@ -346,7 +351,7 @@ mod tests {
     fn applying_double_quarter_turn_is_the_same_as_single_half_turn() {
         assert_eq!(
             FaceletCube::default().apply_move(&Move(Position::Front, Direction::Half)),
-            FaceletCube::default().apply_moves(&vec![
+            FaceletCube::default().apply_moves(&[
                 Move(Position::Front, Direction::Normal),
                 Move(Position::Front, Direction::Normal)
             ])
@@ -354,10 +359,29 @@ mod tests {
 
         assert_eq!(
             FaceletCube::default().apply_move(&Move(Position::Front, Direction::Half)),
-            FaceletCube::default().apply_moves(&vec![
+            FaceletCube::default().apply_moves(&[
                 Move(Position::Front, Direction::Prime),
                 Move(Position::Front, Direction::Prime)
             ])
         );
     }
+
+    #[test]
+    fn verifies_facelets() {
+        assert_eq!(Ok(()), FaceletCube::random(50).verify());
+
+        let invalid = |facelets: &str| facelets.parse::<FaceletCube>().unwrap().verify();
+        assert_eq!(
+            Err("Each colour must appear on nine facelets"),
+            invalid("WWWWWWWWWWRRRRRRRRGGGGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB")
+        );
+        assert_eq!(
+            Err("Facelets do not form valid pieces"),
+            invalid("WWWWWWWWWRRRRRRRRRGGGGGGGGGYYYYYYYYYBOOOOOOOOOBBBBBBBB")
+        );
+        assert_eq!(
+            Err("Corner twist is invalid"),
+            invalid("WWWWWWWWGWRRRRRRRRGGRGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB")
+        );
+    }
 }
@@ -0,0 +1,238 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::facelet_cube::{Colour, FaceletCube};
+use Colour::*;
+
+static FACES: [Colour; 6] = [U, R, F, D, L, B];
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub(crate) struct FaceOrder([Colour; 6]);
+
+impl FaceOrder {
+    pub(crate) const URFDLB: FaceOrder = FaceOrder([U, R, F, D, L, B]);
+}
+
+impl FromStr for FaceOrder {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let faces = s
+            .chars()
+            .map(|face| match face {
+                'U' => Ok(U),
+                'R' => Ok(R),
+                'F' => Ok(F),
+                'D' => Ok(D),
+                'L' => Ok(L),
+                'B' => Ok(B),
+                _ => Err("Unknown face"),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if faces.len() != 6 || FACES.iter().any(|face| !faces.contains(face)) {
+            return Err("Face order must contain each face once");
+        }
+
+        let mut order = [U; 6];
+        order.copy_from_slice(&faces);
+        Ok(FaceOrder(order))
+    }
+}
+
+impl fmt::Display for FaceOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for face in self.0.iter() {
+            write!(f, "{:?}", face)?;
+        }
+        Ok(())
+    }
+}
+
+/// The character used for each face's colour, given in URFDLB order.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub(crate) struct ColourScheme([char; 6]);
+
+impl ColourScheme {
+    pub(crate) const WESTERN: ColourScheme = ColourScheme(['W', 'R', 'G', 'Y', 'O', 'B']);
+    pub(crate) const FACE_LETTERS: ColourScheme = ColourScheme(['U', 'R', 'F', 'D', 'L', 'B']);
+
+    pub(crate) fn to_char(self, colour: Colour) -> char {
+        self.0[colour as usize]
+    }
+
+    fn to_colour(self, c: char) -> Result<Colour, &'static str> {
+        self.0
+            .iter()
+            .position(|&sc| sc == c)
+            .map(|idx| FACES[idx])
+            .ok_or("Unknown facelet colour")
+    }
+}
+
+impl FromStr for ColourScheme {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.chars().collect();
+
+        if chars.len() != 6 {
+            return Err("Colour scheme must give a colour for each face");
+        }
+        if (0..6).any(|i| chars[i + 1..].contains(&chars[i])) {
+            return Err("Colour scheme colours must be distinct");
+        }
+
+        let mut scheme = ['\0'; 6];
+        scheme.copy_from_slice(&chars);
+        Ok(ColourScheme(scheme))
+    }
+}
+
+impl fmt::Display for ColourScheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.iter().collect::<String>())
+    }
+}
+
+/// The textual formats a facelet cube can be read from and written to; 54 characters made up
+/// of each face's nine stickers (read left-to-right, top-to-bottom on the standard net) in the
+/// given face order, with each sticker written as its face's colour character.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub(crate) struct FaceletFormat {
+    pub(crate) order: FaceOrder,
+    pub(crate) scheme: ColourScheme,
+}
+
+impl Default for FaceletFormat {
+    fn default() -> Self {
+        FaceletFormat {
+            order: FaceOrder::URFDLB,
+            scheme: ColourScheme::WESTERN,
+        }
+    }
+}
+
+impl FaceletFormat {
+    /// The face letter format used by Kociemba's two-phase solver.
+    pub(crate) const KOCIEMBA: FaceletFormat = FaceletFormat {
+        order: FaceOrder::URFDLB,
+        scheme: ColourScheme::FACE_LETTERS,
+    };
+
+    /// Infers the colour scheme of the given facelets from their centre stickers.
+    pub(crate) fn from_centres(order: FaceOrder, facelets: &str) -> Result<Self, &'static str> {
+        let chars: Vec<char> = facelets.chars().collect();
+        if chars.len() != 54 {
+            return Err("Invalid facelet cube representation");
+        }
+
+        let mut scheme = ['\0'; 6];
+        for (idx, face) in order.0.iter().enumerate() {
+            scheme[*face as usize] = chars[idx * 9 + 4];
+        }
+
+        Ok(FaceletFormat {
+            order,
+            scheme: scheme
+                .iter()
+                .collect::<String>()
+                .parse()
+                .map_err(|_| "Centre colours must be distinct")?,
+        })
+    }
+
+    pub(crate) fn parse(&self, facelets: &str) -> Result<FaceletCube, &'static str> {
+        let chars: Vec<char> = facelets.chars().collect();
+        if chars.len() != 54 {
+            return Err("Invalid facelet cube representation");
+        }
+
+        let mut state = [U; 54];
+        for (idx, face) in self.order.0.iter().enumerate() {
+            for sticker in 0..9 {
+                state[*face as usize * 9 + sticker] =
+                    self.scheme.to_colour(chars[idx * 9 + sticker])?;
+            }
+        }
+
+        Ok(FaceletCube { state })
+    }
+
+    pub(crate) fn format(&self, cube: &FaceletCube) -> String {
+        self.order
+            .0
+            .iter()
+            .flat_map(|&face| cube.state[face as usize * 9..face as usize * 9 + 9].iter())
+            .map(|&colour| self.scheme.to_char(colour))
+            .collect()
+    }
+
+    /// Rewrites facelets given in this format into another.
+    pub(crate) fn convert(
+        &self,
+        facelets: &str,
+        to: &FaceletFormat,
+    ) -> Result<String, &'static str> {
+        Ok(to.format(&self.parse(facelets)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::moves::{Direction, Move, Position};
+
+    #[test]
+    fn default_format_matches_display() {
+        let cube = FaceletCube::random(100);
+        assert_eq!(cube.to_string(), FaceletFormat::default().format(&cube));
+        assert_eq!(Ok(cube), FaceletFormat::default().parse(&cube.to_string()));
+    }
+
+    #[test]
+    fn converts_to_kociemba_format() {
+        let cube = FaceletCube::default().apply_move(&Move(Position::Right, Direction::Normal));
+
+        assert_eq!(
+            Ok("UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB".to_string()),
+            FaceletFormat::default().convert(&cube.to_string(), &FaceletFormat::KOCIEMBA)
+        );
+    }
+
+    #[test]
+    fn reorders_faces() {
+        let format = FaceletFormat {
+            order: "ULFRBD".parse().unwrap(),
+            scheme: ColourScheme::WESTERN,
+        };
+
+        assert_eq!(
+            "WWWWWWWWWOOOOOOOOOGGGGGGGGGRRRRRRRRRBBBBBBBBBYYYYYYYYY",
+            format.format(&FaceletCube::default())
+        );
+    }
+
+    #[test]
+    fn infers_scheme_from_centres() {
+        let cube = FaceletCube::random(100);
+        let order: FaceOrder = "ULFRBD".parse().unwrap();
+        let scheme: ColourScheme = "WOGBRY".parse().unwrap();
+        let facelets = FaceletFormat { order, scheme }.format(&cube);
+
+        let format = FaceletFormat::from_centres(order, &facelets).unwrap();
+
+        assert_eq!(scheme, format.scheme);
+        assert_eq!(Ok(cube), format.parse(&facelets));
+    }
+
+    #[test]
+    fn rejects_invalid_formats() {
+        assert!("URFDL".parse::<FaceOrder>().is_err());
+        assert!("URFDLU".parse::<FaceOrder>().is_err());
+        assert!("WWGYOB".parse::<ColourScheme>().is_err());
+        assert!(FaceletFormat::from_centres(FaceOrder::URFDLB, &"W".repeat(54)).is_err());
+        assert!(FaceletFormat::KOCIEMBA.parse(&"W".repeat(54)).is_err());
+    }
+}
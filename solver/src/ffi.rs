@@ -0,0 +1,345 @@
+//! A C ABI for native callers, declared in `include/rubik_cube_solver.h`.
+//!
+//! Cubes are opaque handles created by `rcs_cube_new` or `rcs_cube_parse` and released with
+//! `rcs_cube_free`; strings returned by the library are released with `rcs_string_free`. Every
+//! fallible call returns an `RcsStatus`, and on failure `rcs_last_error` describes the error.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use crate::cube::Cube;
+use crate::cubie_cube::CubieCube;
+use crate::facelet_cube::FaceletCube;
+use crate::moves::{Move, MoveSequence};
+use crate::subgroup::solve_with_moves;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RcsStatus {
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullArgument = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 2,
+    InvalidFacelets = 3,
+    InvalidMove = 4,
+    /// The cube can not be reached from solved.
+    UnsolvableCube = 5,
+    /// The cube can not be solved using only the permitted moves.
+    UnreachableCube = 6,
+}
+
+/// A facelet cube, only ever handled through a pointer.
+pub struct RcsCube(FaceletCube);
+
+#[repr(C)]
+pub struct RcsSolveOptions {
+    /// Space separated moves the solution is restricted to (e.g. "R R' R2 U U' U2"), or null to
+    /// permit every move. Only face turns are supported; slice moves such as M are rejected with
+    /// `RCS_STATUS_INVALID_MOVE`.
+    pub permitted_moves: *const c_char,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+struct Failure(RcsStatus, &'static str);
+
+fn run(call: impl FnOnce() -> Result<(), Failure>) -> RcsStatus {
+    match call() {
+        Ok(()) => RcsStatus::Ok,
+        Err(Failure(status, message)) => {
+            LAST_ERROR.with(|error| *error.borrow_mut() = CString::new(message).ok());
+            status
+        }
+    }
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> Result<&'a str, Failure> {
+    if s.is_null() {
+        return Err(Failure(
+            RcsStatus::NullArgument,
+            "Argument must not be null",
+        ));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| Failure(RcsStatus::InvalidUtf8, "Argument must be valid UTF-8"))
+}
+
+unsafe fn to_cube<'a>(cube: *const RcsCube) -> Result<&'a FaceletCube, Failure> {
+    cube.as_ref()
+        .map(|cube| &cube.0)
+        .ok_or(Failure(RcsStatus::NullArgument, "Cube must not be null"))
+}
+
+fn parse_moves(moves: &str) -> Result<Vec<Move>, Failure> {
+    moves
+        .parse::<MoveSequence>()
+        .map(|sequence| sequence.0)
+        .map_err(|err| Failure(RcsStatus::InvalidMove, err))
+}
+
+fn to_c_string(s: String) -> *mut c_char {
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Creates a solved cube.
+#[no_mangle]
+pub extern "C" fn rcs_cube_new() -> *mut RcsCube {
+    Box::into_raw(Box::new(RcsCube(FaceletCube::default())))
+}
+
+/// Parses a cube from its 54 facelets, writing the new cube to `out` on success.
+///
+/// # Safety
+///
+/// `facelets` must be a null-terminated string and `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn rcs_cube_parse(
+    facelets: *const c_char,
+    out: *mut *mut RcsCube,
+) -> RcsStatus {
+    run(|| {
+        if out.is_null() {
+            return Err(Failure(RcsStatus::NullArgument, "Output must not be null"));
+        }
+        let cube = to_str(facelets)?
+            .parse::<FaceletCube>()
+            .map_err(|err| Failure(RcsStatus::InvalidFacelets, err))?;
+        *out = Box::into_raw(Box::new(RcsCube(cube)));
+        Ok(())
+    })
+}
+
+/// Releases a cube; null is ignored.
+///
+/// # Safety
+///
+/// `cube` must have come from this library and not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn rcs_cube_free(cube: *mut RcsCube) {
+    if !cube.is_null() {
+        drop(Box::from_raw(cube));
+    }
+}
+
+/// The cube's 54 facelets, to be released with `rcs_string_free`; null if `cube` is null.
+///
+/// # Safety
+///
+/// `cube` must be null or a live cube from this library.
+#[no_mangle]
+pub unsafe extern "C" fn rcs_cube_to_string(cube: *const RcsCube) -> *mut c_char {
+    match to_cube(cube) {
+        Ok(cube) => to_c_string(cube.to_string()),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Checks the cube can be reached from solved, returning `RCS_STATUS_UNSOLVABLE_CUBE` if not.
+///
+/// # Safety
+///
+/// `cube` must be null or a live cube from this library.
+#[no_mangle]
+pub unsafe extern "C" fn rcs_cube_validate(cube: *const RcsCube) -> RcsStatus {
+    run(|| {
+        to_cube(cube)?
+            .verify()
+            .map_err(|err| Failure(RcsStatus::UnsolvableCube, err))
+    })
+}
+
+/// Applies space separated moves (e.g. "R U R' U'") to the cube, leaving it untouched if any
+/// move is invalid.
+///
+/// # Safety
+///
+/// `cube` must be null or a live cube from this library, and `moves` a null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rcs_cube_apply_moves(
+    cube: *mut RcsCube,
+    moves: *const c_char,
+) -> RcsStatus {
+    run(|| {
+        let moves = parse_moves(to_str(moves)?)?;
+        let cube = cube
+            .as_mut()
+            .ok_or(Failure(RcsStatus::NullArgument, "Cube must not be null"))?;
+        cube.0 = cube.0.apply_moves(&moves);
+        Ok(())
+    })
+}
+
+/// Solves the cube, writing the space separated solution to `solution` on success, to be
+/// released with `rcs_string_free`. `options` may be null to use the defaults.
+///
+/// # Safety
+///
+/// `cube` must be null or a live cube from this library, `options` null or a valid options
+/// struct, and `solution` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn rcs_cube_solve(
+    cube: *const RcsCube,
+    options: *const RcsSolveOptions,
+    solution: *mut *mut c_char,
+) -> RcsStatus {
+    run(|| {
+        if solution.is_null() {
+            return Err(Failure(RcsStatus::NullArgument, "Output must not be null"));
+        }
+        let cube = to_cube(cube)?;
+        cube.verify()
+            .map_err(|err| Failure(RcsStatus::UnsolvableCube, err))?;
+
+        let moves = match options.as_ref() {
+            Some(options) if !options.permitted_moves.is_null() => solve_with_moves(
+                &CubieCube::from(*cube),
+                &parse_moves(to_str(options.permitted_moves)?)?,
+            )
+            .map_err(|err| Failure(RcsStatus::UnreachableCube, err))?,
+            _ => cube
+                .solve()
+                .ok_or(Failure(RcsStatus::UnsolvableCube, "Cube has no solution"))?,
+        };
+
+        *solution = to_c_string(
+            moves
+                .iter()
+                .map(|action| action.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+        Ok(())
+    })
+}
+
+/// Releases a string returned by this library; null is ignored.
+///
+/// # Safety
+///
+/// `s` must have come from this library and not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn rcs_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Describes the last failure on the calling thread, or null if nothing has failed. The string
+/// is owned by the library and stays valid until the next failure on the same thread.
+#[no_mangle]
+pub extern "C" fn rcs_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |error| error.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::Position::{Right, Up};
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    unsafe fn take_string(s: *mut c_char) -> String {
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_string();
+        rcs_string_free(s);
+        owned
+    }
+
+    #[test]
+    fn applies_moves_and_solves() {
+        unsafe {
+            let cube = rcs_cube_new();
+            assert_eq!(
+                RcsStatus::Ok,
+                rcs_cube_apply_moves(cube, c("R U R' U'").as_ptr())
+            );
+
+            let mut solution = ptr::null_mut();
+            assert_eq!(
+                RcsStatus::Ok,
+                rcs_cube_solve(cube, ptr::null(), &mut solution)
+            );
+            let solution = take_string(solution);
+
+            assert_eq!(
+                RcsStatus::Ok,
+                rcs_cube_apply_moves(cube, c(&solution).as_ptr())
+            );
+            assert_eq!(
+                FaceletCube::default().to_string(),
+                take_string(rcs_cube_to_string(cube))
+            );
+            rcs_cube_free(cube);
+        }
+    }
+
+    #[test]
+    fn solves_with_permitted_moves() {
+        unsafe {
+            let cube = rcs_cube_new();
+            rcs_cube_apply_moves(cube, c("R U").as_ptr());
+            let permitted_moves = c("R U");
+            let options = RcsSolveOptions {
+                permitted_moves: permitted_moves.as_ptr(),
+            };
+
+            let mut solution = ptr::null_mut();
+            assert_eq!(RcsStatus::Ok, rcs_cube_solve(cube, &options, &mut solution));
+            assert!(take_string(solution)
+                .parse::<MoveSequence>()
+                .unwrap()
+                .0
+                .iter()
+                .all(|action| action.0 == Right || action.0 == Up));
+
+            rcs_cube_apply_moves(cube, c("F").as_ptr());
+            assert_eq!(
+                RcsStatus::UnreachableCube,
+                rcs_cube_solve(cube, &options, &mut solution)
+            );
+            rcs_cube_free(cube);
+        }
+    }
+
+    #[test]
+    fn reports_errors() {
+        unsafe {
+            let mut cube = ptr::null_mut();
+            assert_eq!(
+                RcsStatus::InvalidFacelets,
+                rcs_cube_parse(c("WWW").as_ptr(), &mut cube)
+            );
+            assert_eq!(
+                "Invalid facelet cube representation",
+                CStr::from_ptr(rcs_last_error()).to_str().unwrap()
+            );
+
+            assert_eq!(
+                RcsStatus::Ok,
+                rcs_cube_parse(
+                    c("WWWWWWWWGWRRRRRRRRGGRGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB").as_ptr(),
+                    &mut cube
+                )
+            );
+            assert_eq!(RcsStatus::UnsolvableCube, rcs_cube_validate(cube));
+            assert_eq!(
+                RcsStatus::InvalidMove,
+                rcs_cube_apply_moves(cube, c("R X").as_ptr())
+            );
+            assert_eq!(RcsStatus::NullArgument, rcs_cube_validate(ptr::null()));
+            rcs_cube_free(cube);
+        }
+    }
+}
@@ -1,16 +1,73 @@
+mod alg_url;
+mod cases;
 mod cube;
 mod cubie_cube;
+mod face_images;
 mod facelet_cube;
+mod facelet_format;
+#[cfg(feature = "ffi")]
+mod ffi;
 mod moves;
+mod patterns;
+mod phase_tables;
 mod pochmann_solver;
+#[cfg(feature = "python")]
+mod python;
+mod reconstruction;
+mod robot;
+#[cfg(feature = "rpc")]
+mod rpc;
+mod scramble;
+#[cfg(feature = "server")]
+mod server;
+#[cfg(feature = "smart-cube")]
+mod smart_cube;
+mod sticker_colours;
+mod subgroup;
+mod subsets;
+mod svg;
+mod terminal;
+mod wasm_api;
 
-use crate::cube::Cube;
-use crate::cubie_cube::CubieCube;
-use crate::facelet_cube::FaceletCube;
-use crate::moves::Move;
-use crate::pochmann_solver::solve;
+use crate::facelet_format::FaceletFormat;
+use crate::pochmann_solver::{solve, solve_in_phases};
+use crate::wasm_api::to_js_moves;
 use wasm_bindgen::prelude::*;
 
+pub use crate::alg_url::{alg_cubing_net_url, parse_alg_url, twizzle_url, SharedAlg};
+pub use crate::cases::{case_cube, case_scramble, cases, find_case, Case, CaseOptions};
+pub use crate::cube::Cube;
+pub use crate::cubie_cube::CubieCube;
+pub use crate::face_images::{locate_face_grid, scan_cube, FaceGrid, FaceImage};
+pub use crate::facelet_cube::FaceletCube;
+pub use crate::moves::{Direction, Move, MoveSequence, Position, Slice, Turn};
+pub use crate::patterns::{
+    find_pattern, matching_pattern, patterns, Pattern, ANACONDA, ANACONDA_FACELETS, CHECKERBOARD,
+    CHECKERBOARD_FACELETS, CUBE_IN_CUBE, CUBE_IN_CUBE_FACELETS, CUBE_IN_CUBE_IN_CUBE,
+    CUBE_IN_CUBE_IN_CUBE_FACELETS, FOUR_SPOT, FOUR_SPOT_FACELETS, SIX_SPOT, SIX_SPOT_FACELETS,
+    SUPERFLIP, SUPERFLIP_FACELETS, TETRIS, TETRIS_FACELETS,
+};
+pub use crate::phase_tables::{install_phase_tables, PhaseTables};
+pub use crate::reconstruction::{Method, Reconstruction, Step, TimedMove};
+pub use crate::robot::{Axis, RobotAction, RobotModel, RobotPlan, Rotation};
+#[cfg(feature = "rpc")]
+pub use crate::rpc::serve_rpc;
+pub use crate::scramble::{
+    filtered_scramble, parse_scramble, random_scramble, scrambled_cube, Event, ScrambleFilter,
+    DEFAULT_SCRAMBLE_LENGTH,
+};
+#[cfg(feature = "server")]
+pub use crate::server::{serve, ServerConfig};
+#[cfg(feature = "smart-cube")]
+pub use crate::smart_cube::{
+    GanDecoder, GiikerDecoder, GoCubeDecoder, SmartCubeDecoder, SmartCubeEvent,
+};
+pub use crate::sticker_colours::{classify_stickers, ColourClassification, StickerSample};
+pub use crate::subgroup::{solve_with_moves, solve_with_turns};
+pub use crate::subsets::{subset_scramble, Subset};
+pub use crate::svg::{render as render_svg, Arrow, Stickering, SvgOptions, View};
+pub use crate::terminal::{render as render_terminal, TerminalStyle};
+
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
 #[cfg(feature = "wee_alloc")]
@@ -22,17 +79,121 @@ pub fn rand_cube() -> String {
     FaceletCube::random(100).to_string()
 }
 
+/// A random WCA-style scramble, 25 moves long by default.
+#[wasm_bindgen]
+pub fn rand_scramble(length: Option<usize>) -> js_sys::Array {
+    to_js_moves(&random_scramble(length.unwrap_or(DEFAULT_SCRAMBLE_LENGTH)))
+}
+
+/// A random WCA-style scramble for the event (e.g. "333oh"), rejecting those leaving the cube
+/// too close to solved or with too much of it already built.
+#[wasm_bindgen]
+pub fn rand_event_scramble(event: String) -> Result<js_sys::Array, JsValue> {
+    let filter = ScrambleFilter::for_event(event.parse()?);
+    Ok(to_js_moves(&filtered_scramble(
+        DEFAULT_SCRAMBLE_LENGTH,
+        &filter,
+    )?))
+}
+
+/// A scramble setting up a uniformly random state of the subset (e.g. "zbll"), for training a
+/// single step.
+#[wasm_bindgen]
+pub fn rand_subset_scramble(subset: String) -> Result<js_sys::Array, JsValue> {
+    Ok(to_js_moves(&subset_scramble(subset.parse()?)))
+}
+
+/// The catalogue of cases which can be drilled, each with its set, name and algorithm.
+#[wasm_bindgen]
+pub fn case_catalogue() -> Result<JsValue, JsValue> {
+    js_sys::JSON::parse(&serde_json::to_string(cases()).unwrap())
+}
+
+/// A scramble setting up the named case (e.g. "PLL Ua"), which is found by solving the case
+/// rather than reversing its algorithm, optionally with a random AUF and from a random side.
+#[wasm_bindgen]
+pub fn drill_case_scramble(
+    case: String,
+    random_auf: bool,
+    random_rotation: bool,
+) -> Result<js_sys::Array, JsValue> {
+    let case = find_case(&case).ok_or("Unknown case")?;
+    let options = CaseOptions {
+        random_auf,
+        random_rotation,
+    };
+    Ok(to_js_moves(&case_scramble(case, options)))
+}
+
+/// The library of named patterns, each with its name, optimal sequence and facelets.
+#[wasm_bindgen]
+pub fn pattern_library() -> Result<JsValue, JsValue> {
+    js_sys::JSON::parse(&serde_json::to_string(patterns()).unwrap())
+}
+
+/// The shortest sequence the solver finds for the named pattern (e.g. "Superflip").
+#[wasm_bindgen]
+pub fn pattern_solver_sequence(pattern: String) -> Result<js_sys::Array, JsValue> {
+    let pattern = find_pattern(&pattern).ok_or("Unknown pattern")?;
+    Ok(to_js_moves(&pattern.solver_sequence()))
+}
+
+/// The name of the pattern the cube shows, held in any way or mirrored, if any.
+#[wasm_bindgen]
+pub fn match_cube_pattern(cube: String) -> Result<Option<String>, JsValue> {
+    let cube = cube.parse::<FaceletCube>()?;
+    cube.verify()?;
+    Ok(matching_pattern(&CubieCube::from(cube)).map(|pattern| pattern.name.to_string()))
+}
+
+/// Checks the space separated scramble follows the WCA's conventions, returning its moves.
+#[wasm_bindgen]
+pub fn validate_scramble(scramble: String) -> Result<js_sys::Array, JsValue> {
+    Ok(to_js_moves(&parse_scramble(&scramble)?))
+}
+
+/// The facelets of the cube the space separated scramble leaves a solved cube in.
+#[wasm_bindgen]
+pub fn scramble_cube(scramble: String) -> Result<String, JsValue> {
+    Ok(scrambled_cube(&parse_scramble(&scramble)?).to_string())
+}
+
 #[wasm_bindgen]
 pub fn solve_cube(cube: String) -> Result<js_sys::Array, JsValue> {
     match solve(&CubieCube::from(cube.parse::<FaceletCube>()?)) {
-        Some(solution) => Ok(solution
-            .into_iter()
-            .map(|mv| JsValue::from_str(&format!("{}", mv)))
-            .collect::<js_sys::Array>()),
+        Some(solution) => Ok(to_js_moves(&solution)),
         None => Err(JsValue::from_str("Cube is unsolveable")),
     }
 }
 
+#[wasm_bindgen]
+pub fn solve_cube_in_phases(cube: String) -> Result<JsValue, JsValue> {
+    let cube = cube.parse::<FaceletCube>()?;
+    cube.verify()?;
+    match solve_in_phases(&CubieCube::from(cube)) {
+        Some(solution) => js_sys::JSON::parse(&serde_json::to_string(&solution).unwrap()),
+        None => Err(JsValue::from_str("Cube is unsolveable")),
+    }
+}
+
+/// Finds a short solution using only the permitted turns (e.g. `["R", "U"]` or
+/// `["M", "U"]`). As slice turns carry the centres around, the cube may be given held with its
+/// centres anywhere the whole cube can be turned to.
+#[wasm_bindgen]
+pub fn solve_cube_with_moves(
+    cube: String,
+    permitted_moves: js_sys::Array,
+) -> Result<js_sys::Array, JsValue> {
+    let permitted_turns: Vec<Turn> = permitted_moves
+        .iter()
+        .map(|mv| (mv.as_string().unwrap_or("".to_string())).parse())
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(to_js_moves(&solve_with_turns(
+        &cube.parse::<FaceletCube>()?,
+        &permitted_turns,
+    )?))
+}
+
 #[wasm_bindgen]
 pub fn apply_cube_moves(cube: String, moves: js_sys::Array) -> Result<String, JsValue> {
     let actions: Vec<Move> = moves
@@ -44,3 +205,117 @@ pub fn apply_cube_moves(cube: String, moves: js_sys::Array) -> Result<String, Js
         .apply_moves(&actions)
         .to_string())
 }
+
+#[wasm_bindgen]
+pub fn to_kociemba_facelets(cube: String) -> Result<String, JsValue> {
+    Ok(FaceletFormat::default().convert(&cube, &FaceletFormat::KOCIEMBA)?)
+}
+
+#[wasm_bindgen]
+pub fn from_kociemba_facelets(facelets: String) -> Result<String, JsValue> {
+    Ok(FaceletFormat::KOCIEMBA.convert(&facelets, &FaceletFormat::default())?)
+}
+
+/// Converts facelets between face orders (e.g. "URFDLB") and colour schemes (a colour character
+/// for each of the U, R, F, D, L and B faces, e.g. "WRGYOB"), inferring the source scheme from
+/// the centre stickers when it is not given.
+#[wasm_bindgen]
+pub fn convert_cube_facelets(
+    facelets: String,
+    from_order: String,
+    from_scheme: Option<String>,
+    to_order: String,
+    to_scheme: String,
+) -> Result<String, JsValue> {
+    let from = match from_scheme {
+        Some(scheme) => FaceletFormat {
+            order: from_order.parse()?,
+            scheme: scheme.parse()?,
+        },
+        None => FaceletFormat::from_centres(from_order.parse()?, &facelets)?,
+    };
+    let to = FaceletFormat {
+        order: to_order.parse()?,
+        scheme: to_scheme.parse()?,
+    };
+    Ok(from.convert(&facelets, &to)?)
+}
+
+/// Renders the cube as an SVG "net" or "isometric" view, optionally masking stickers (e.g.
+/// "f2l"), drawing comma-separated arrows (e.g. "U1U3,U3U1") and overriding the comma-separated
+/// face colours (in URFDLB order).
+#[wasm_bindgen]
+pub fn render_cube_svg(
+    cube: String,
+    view: String,
+    stickering: Option<String>,
+    arrows: Option<String>,
+    colours: Option<String>,
+) -> Result<String, JsValue> {
+    let mut options = SvgOptions {
+        view: view.parse()?,
+        ..SvgOptions::default()
+    };
+    if let Some(stickering) = stickering {
+        options.stickering = stickering.parse()?;
+    }
+    if let Some(arrows) = arrows {
+        options.arrows = arrows
+            .split(',')
+            .map(|arrow| arrow.trim().parse())
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+    if let Some(colours) = colours {
+        let colours: Vec<&str> = colours.split(',').map(|colour| colour.trim()).collect();
+        if colours.len() != 6 {
+            return Err(JsValue::from_str("A colour must be given for each face"));
+        }
+        for (face, colour) in colours.into_iter().enumerate() {
+            options.colours[face] = colour.to_string();
+        }
+    }
+    Ok(render_svg(&cube.parse::<FaceletCube>()?, &options))
+}
+
+#[wasm_bindgen]
+pub fn load_phase_tables(bytes: &[u8]) -> Result<(), JsValue> {
+    install_phase_tables(PhaseTables::from_bytes(bytes)?);
+    Ok(())
+}
+
+/// Classifies the RGB samples (three bytes per sticker, in URFDLB facelet order) of the 54
+/// stickers, returning the cube's facelets along with the confidence in each sticker's colour.
+#[wasm_bindgen]
+pub fn classify_sticker_colours(rgb: Vec<u8>) -> Result<JsValue, JsValue> {
+    let samples: Vec<StickerSample> = rgb
+        .chunks(3)
+        .map(|sample| match *sample {
+            [r, g, b] => Ok(StickerSample::Rgb(r, g, b)),
+            _ => Err("Each sample must have three channels"),
+        })
+        .collect::<Result<_, _>>()?;
+    let classification = classify_stickers(&samples)?;
+    js_sys::JSON::parse(
+        &serde_json::json!({
+            "facelets": classification.cube.to_string(),
+            "confidence": classification.confidence,
+        })
+        .to_string(),
+    )
+}
+
+/// Locates the sticker grid in a photo of a face (as a canvas' RGBA `ImageData`), returning the
+/// RGB samples of its nine stickers in reading order, ready for `classify_sticker_colours`.
+#[wasm_bindgen]
+pub fn sample_face_stickers(
+    width: usize,
+    height: usize,
+    rgba: Vec<u8>,
+) -> Result<Vec<u8>, JsValue> {
+    let grid = locate_face_grid(&FaceImage::rgba(width, height, &rgba)?)?;
+    Ok(grid
+        .samples
+        .iter()
+        .flat_map(|sample| sample.to_rgb_bytes())
+        .collect())
+}
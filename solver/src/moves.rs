@@ -1,3 +1,4 @@
+use serde::{Serialize, Serializer};
 use std::fmt;
 use std::str::FromStr;
 
@@ -6,7 +7,7 @@ use Position::*;
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
 #[repr(u8)]
-pub(crate) enum Position {
+pub enum Position {
     Up,
     Down,
     Left,
@@ -15,16 +16,30 @@ pub(crate) enum Position {
     Back,
 }
 
+impl Position {
+    /// The face on the other side of the cube.
+    pub fn opposite(self) -> Self {
+        match self {
+            Up => Down,
+            Down => Up,
+            Left => Right,
+            Right => Left,
+            Front => Back,
+            Back => Front,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
 #[repr(u8)]
-pub(crate) enum Direction {
+pub enum Direction {
     Normal,
     Prime,
     Half,
 }
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
-pub(crate) struct Move(pub Position, pub Direction);
+pub struct Move(pub Position, pub Direction);
 
 impl FromStr for Move {
     type Err = &'static str;
@@ -49,6 +64,11 @@ impl FromStr for Move {
             "D" => Ok(Self(Down, Normal)),
             "D'" => Ok(Self(Down, Prime)),
             "D2" => Ok(Self(Down, Half)),
+            // Slice moves turn the centres, which are fixed in both cube models; only a `Turn`
+            // can describe them.
+            "M" | "M'" | "M2" | "E" | "E'" | "E2" | "S" | "S'" | "S2" => {
+                Err("Slice moves (M, E and S) are not supported")
+            }
             _ => Err("Unknown move"),
         }
     }
@@ -79,8 +99,47 @@ impl fmt::Display for Move {
     }
 }
 
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Moves written one after another, separated by spaces (e.g. "R U R' U'").
+#[derive(Debug, Clone, Default, Hash, Eq, PartialEq)]
+pub struct MoveSequence(pub Vec<Move>);
+
+impl FromStr for MoveSequence {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_whitespace()
+            .map(|action| action.parse())
+            .collect::<Result<_, _>>()
+            .map(MoveSequence)
+    }
+}
+
+impl fmt::Display for MoveSequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, action) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", action)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&[Move]> for MoveSequence {
+    fn from(moves: &[Move]) -> Self {
+        MoveSequence(moves.to_vec())
+    }
+}
+
 impl Move {
-    pub(crate) fn available() -> [Self; 18] {
+    pub fn available() -> [Self; 18] {
         [
             Move(Up, Normal),
             Move(Up, Prime),
@@ -103,7 +162,7 @@ impl Move {
         ]
     }
 
-    pub(crate) fn inverse(self) -> Self {
+    pub fn inverse(self) -> Self {
         match self {
             Move(position, Normal) => Move(position, Prime),
             Move(position, Prime) => Move(position, Normal),
@@ -111,3 +170,123 @@ impl Move {
         }
     }
 }
+
+/// The middle layers, each turned in the same direction as one of the faces beside it.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+#[repr(u8)]
+pub enum Slice {
+    /// Between L and R, turned as L.
+    Middle,
+    /// Between U and D, turned as D.
+    Equator,
+    /// Between F and B, turned as F.
+    Standing,
+}
+
+/// A turn of a single layer: a face, or a middle layer which carries its centres along with it.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum Turn {
+    Face(Move),
+    Slice(Slice, Direction),
+}
+
+impl Turn {
+    pub fn inverse(self) -> Self {
+        match self {
+            Turn::Face(action) => Turn::Face(action.inverse()),
+            Turn::Slice(slice, Normal) => Turn::Slice(slice, Prime),
+            Turn::Slice(slice, Prime) => Turn::Slice(slice, Normal),
+            Turn::Slice(..) => self,
+        }
+    }
+}
+
+impl From<Move> for Turn {
+    fn from(action: Move) -> Self {
+        Turn::Face(action)
+    }
+}
+
+impl FromStr for Turn {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let slice = match s.get(..1) {
+            Some("M") => Slice::Middle,
+            Some("E") => Slice::Equator,
+            Some("S") => Slice::Standing,
+            _ => return s.parse().map(Turn::Face),
+        };
+        let direction = match &s[1..] {
+            "" => Normal,
+            "'" => Prime,
+            "2" => Half,
+            _ => return Err("Unknown move"),
+        };
+        Ok(Turn::Slice(slice, direction))
+    }
+}
+
+impl fmt::Display for Turn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Turn::Face(action) => write!(f, "{}", action),
+            Turn::Slice(slice, direction) => {
+                let layer = match slice {
+                    Slice::Middle => "M",
+                    Slice::Equator => "E",
+                    Slice::Standing => "S",
+                };
+                let suffix = match direction {
+                    Normal => "",
+                    Prime => "'",
+                    Half => "2",
+                };
+                write!(f, "{}{}", layer, suffix)
+            }
+        }
+    }
+}
+
+impl Serialize for Turn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences_round_trip() {
+        let sequence: MoveSequence = " R  U' F2\tD ".parse().unwrap();
+
+        assert_eq!(
+            vec![
+                Move(Right, Normal),
+                Move(Up, Prime),
+                Move(Front, Half),
+                Move(Down, Normal)
+            ],
+            sequence.0
+        );
+        assert_eq!("R U' F2 D", sequence.to_string());
+        assert_eq!("", MoveSequence::default().to_string());
+        assert_eq!(Err("Unknown move"), "R X".parse::<MoveSequence>());
+    }
+
+    #[test]
+    fn turns_round_trip() {
+        for notation in ["R", "U'", "F2", "M", "E'", "S2"].iter() {
+            assert_eq!(*notation, notation.parse::<Turn>().unwrap().to_string());
+        }
+        assert_eq!(
+            Ok(Turn::Slice(Slice::Middle, Normal)),
+            "M'".parse::<Turn>().map(Turn::inverse)
+        );
+        assert_eq!(Ok(Turn::Face(Move(Right, Normal))), "R".parse());
+        assert_eq!(Err("Unknown move"), "M3".parse::<Turn>());
+        assert_eq!(Err("Unknown move"), "Rw".parse::<Turn>());
+    }
+}
@@ -0,0 +1,351 @@
+//! Named patterns, the sequences making them, and recognising them on a cube.
+//!
+//! A pattern held differently or mirrored is still recognised as that pattern: recognition
+//! accepts any of the pattern's 48 symmetric copies, which for chiral patterns (e.g. Tetris and
+//! Anaconda) include their mirror images. A symmetry is described by where it moves
+//! each sticker; applying one to a cube moves its stickers and then recolours them, so the
+//! centres keep their usual colours.
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+use crate::cubie_cube::CubieCube;
+use crate::facelet_cube::Colour::{self, *};
+use crate::facelet_cube::{facelet_location, FaceletCube, Vector};
+use crate::moves::Direction::*;
+use crate::moves::Position::{self, *};
+use crate::moves::{Move, MoveSequence};
+use crate::pochmann_solver::solve;
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize)]
+pub struct Pattern {
+    pub name: &'static str,
+    /// A sequence making the pattern which is optimal in the half turn metric.
+    pub sequence: &'static str,
+    /// The pattern's facelets, as made by the sequence.
+    pub facelets: &'static str,
+}
+
+const PATTERNS: [Pattern; 8] = [
+    Pattern {
+        name: "Superflip",
+        sequence: "U R2 F B R B2 R U2 L B2 R U' D' R2 F R' L B2 U2 F2",
+        facelets: "WBWOWRWGWRWRGRBRYRGWGOGRGYGYGYOYRYBYOWOBOGOYOBWBRBOBYB",
+    },
+    Pattern {
+        name: "Checkerboard",
+        sequence: "U2 D2 F2 B2 L2 R2",
+        facelets: "WYWYWYWYWRORORORORGBGBGBGBGYWYWYWYWYOROROROROBGBGBGBGB",
+    },
+    Pattern {
+        name: "Cube in cube",
+        sequence: "F L F U' R U F2 L2 U' L' B D' B' L2 U",
+        facelets: "GGGGWWGWWRRWRRWWWWRGGRGGRRRBBBYYBYYBYYYOOYOOYOOOOBBOBB",
+    },
+    Pattern {
+        name: "Cube in cube in cube",
+        sequence: "U' L' U' F' R2 B' R F U B2 U B' L U' F U R F'",
+        facelets: "RRRRWWRWGWRGRRGGGGWGRWGGWWWOOOYYOBYOBBBOOBYOBYYYYBBYBO",
+    },
+    Pattern {
+        name: "Six spot",
+        sequence: "U D' R L' F B' U D'",
+        facelets: "GGGGWGGGGWWWWRWWWWRRRRGRRRRBBBBYBBBBYYYYOYYYYOOOOBOOOO",
+    },
+    Pattern {
+        name: "Four spot",
+        sequence: "F2 B2 U D' R2 L2 U D'",
+        facelets: "WWWWWWWWWOOOOROOOOBBBBGBBBBYYYYYYYYYRRRRORRRRGGGGBGGGG",
+    },
+    Pattern {
+        name: "Tetris",
+        sequence: "L R F B U' D' L' R'",
+        facelets: "GGBGWBGBBWYYWRYWWYROORGORROBBGBYGBGGWYYWOYWWYORROBROOR",
+    },
+    Pattern {
+        name: "Anaconda",
+        sequence: "L U B' U' R L' B R' F B' D R D' F'",
+        facelets: "GWGWWGGGGWWWWRRWRWRRRGGRRGRBYBBYYBBBYOYYOOYYYOBOBBOOOO",
+    },
+];
+
+// Each pattern's state, as both cube models, built on first use.
+lazy_static! {
+    pub static ref SUPERFLIP: CubieCube = PATTERNS[0].cube();
+    pub static ref CHECKERBOARD: CubieCube = PATTERNS[1].cube();
+    pub static ref CUBE_IN_CUBE: CubieCube = PATTERNS[2].cube();
+    pub static ref CUBE_IN_CUBE_IN_CUBE: CubieCube = PATTERNS[3].cube();
+    pub static ref SIX_SPOT: CubieCube = PATTERNS[4].cube();
+    pub static ref FOUR_SPOT: CubieCube = PATTERNS[5].cube();
+    pub static ref TETRIS: CubieCube = PATTERNS[6].cube();
+    pub static ref ANACONDA: CubieCube = PATTERNS[7].cube();
+    pub static ref SUPERFLIP_FACELETS: FaceletCube = PATTERNS[0].facelet_cube();
+    pub static ref CHECKERBOARD_FACELETS: FaceletCube = PATTERNS[1].facelet_cube();
+    pub static ref CUBE_IN_CUBE_FACELETS: FaceletCube = PATTERNS[2].facelet_cube();
+    pub static ref CUBE_IN_CUBE_IN_CUBE_FACELETS: FaceletCube = PATTERNS[3].facelet_cube();
+    pub static ref SIX_SPOT_FACELETS: FaceletCube = PATTERNS[4].facelet_cube();
+    pub static ref FOUR_SPOT_FACELETS: FaceletCube = PATTERNS[5].facelet_cube();
+    pub static ref TETRIS_FACELETS: FaceletCube = PATTERNS[6].facelet_cube();
+    pub static ref ANACONDA_FACELETS: FaceletCube = PATTERNS[7].facelet_cube();
+}
+
+/// Every pattern in the library.
+pub fn patterns() -> &'static [Pattern] {
+    &PATTERNS
+}
+
+/// Looks up a pattern by its name, ignoring case.
+pub fn find_pattern(name: &str) -> Option<&'static Pattern> {
+    PATTERNS
+        .iter()
+        .find(|pattern| pattern.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// The pattern the cube shows, held in any way or mirrored.
+pub fn matching_pattern(cube: &CubieCube) -> Option<&'static Pattern> {
+    PATTERNS.iter().find(|pattern| pattern.matches(cube))
+}
+
+impl Pattern {
+    pub fn moves(&self) -> Vec<Move> {
+        let sequence: MoveSequence = self.sequence.parse().expect("Pattern sequences are valid");
+        sequence.0
+    }
+
+    pub fn facelet_cube(&self) -> FaceletCube {
+        self.facelets.parse().expect("Pattern facelets are valid")
+    }
+
+    pub fn cube(&self) -> CubieCube {
+        CubieCube::from(self.facelet_cube())
+    }
+
+    /// Whether the cube shows the pattern, held in any way or mirrored.
+    pub fn matches(&self, cube: &CubieCube) -> bool {
+        let faces = FaceletCube::from(*cube);
+        let pattern = self.facelet_cube();
+        SYMMETRIES
+            .iter()
+            .any(|symmetry| symmetry.apply(&pattern) == faces)
+    }
+
+    /// The shortest sequence the solver finds for the pattern, trying each of its symmetric
+    /// copies and turning the sequence found back. The solver is not optimal, so this is
+    /// usually longer than the pattern's known sequence.
+    pub fn solver_sequence(&self) -> Vec<Move> {
+        let pattern = self.facelet_cube();
+        SYMMETRIES
+            .iter()
+            .filter_map(|symmetry| {
+                let solution = solve(&CubieCube::from(symmetry.apply(&pattern)))?;
+                let inverse = symmetry.inverse();
+                Some(
+                    solution
+                        .iter()
+                        .rev()
+                        .map(|action| inverse.apply_move(&action.inverse()))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .min_by_key(|sequence| sequence.len())
+            .expect("Patterns are solvable")
+    }
+}
+
+const COLOURS: [Colour; 6] = [U, R, F, D, L, B];
+
+/// The faces in the order their facelets are stored.
+const FACES: [Position; 6] = [Up, Right, Front, Down, Left, Back];
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+struct Symmetry {
+    /// Where each facelet is moved to.
+    facelets: [usize; 54],
+    mirrored: bool,
+}
+
+impl Symmetry {
+    fn from_transform(transform: impl Fn(Vector) -> Vector, mirrored: bool) -> Self {
+        let locations: Vec<(Vector, Vector)> = (0..54).map(facelet_location).collect();
+        let mut facelets = [0; 54];
+        for (idx, &(cubie, normal)) in locations.iter().enumerate() {
+            let moved = (transform(cubie), transform(normal));
+            facelets[idx] = locations
+                .iter()
+                .position(|&location| location == moved)
+                .expect("Symmetries move facelets onto facelets");
+        }
+        Symmetry { facelets, mirrored }
+    }
+
+    /// This symmetry followed by the other.
+    fn then(&self, other: &Self) -> Self {
+        let mut facelets = [0; 54];
+        for (idx, &moved) in self.facelets.iter().enumerate() {
+            facelets[idx] = other.facelets[moved];
+        }
+        Symmetry {
+            facelets,
+            mirrored: self.mirrored != other.mirrored,
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        let mut facelets = [0; 54];
+        for (idx, &moved) in self.facelets.iter().enumerate() {
+            facelets[moved] = idx;
+        }
+        Symmetry {
+            facelets,
+            mirrored: self.mirrored,
+        }
+    }
+
+    /// Where the face (in facelet order) is moved to.
+    fn face(&self, face: usize) -> usize {
+        self.facelets[face * 9 + 4] / 9
+    }
+
+    fn apply(&self, cube: &FaceletCube) -> FaceletCube {
+        let mut state = cube.state;
+        for (idx, &colour) in cube.state.iter().enumerate() {
+            state[self.facelets[idx]] = COLOURS[self.face(colour as usize)];
+        }
+        FaceletCube { state }
+    }
+
+    /// The move doing to a cube with the symmetry applied what the given move does to the cube.
+    fn apply_move(&self, action: &Move) -> Move {
+        let face = FACES.iter().position(|&face| face == action.0).unwrap();
+        let direction = match (self.mirrored, action.1) {
+            (true, Normal) => Prime,
+            (true, Prime) => Normal,
+            (_, direction) => direction,
+        };
+        Move(FACES[self.face(face)], direction)
+    }
+}
+
+lazy_static! {
+    /// The 24 ways of holding the cube and their mirror images.
+    static ref SYMMETRIES: Vec<Symmetry> = {
+        let generators = [
+            Symmetry::from_transform(|[x, y, z]| [x, z, -y], false),
+            Symmetry::from_transform(|[x, y, z]| [-z, y, x], false),
+            Symmetry::from_transform(|[x, y, z]| [-x, y, z], true),
+        ];
+        let mut symmetries = vec![Symmetry::from_transform(|vector| vector, false)];
+        let mut idx = 0;
+        while idx < symmetries.len() {
+            for generator in generators.iter() {
+                let symmetry = symmetries[idx].then(generator);
+                if !symmetries.contains(&symmetry) {
+                    symmetries.push(symmetry);
+                }
+            }
+            idx += 1;
+        }
+        symmetries
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+
+    #[test]
+    fn sequences_make_their_patterns() {
+        for pattern in patterns() {
+            assert_eq!(
+                pattern.facelet_cube(),
+                FaceletCube::default().apply_moves(&pattern.moves()),
+                "{}",
+                pattern.name
+            );
+        }
+        assert!(SUPERFLIP.eo.iter().all(|&eo| eo == 1));
+        assert_eq!(CubieCube::default().cp, SUPERFLIP.cp);
+    }
+
+    #[test]
+    fn constants_hold_their_patterns() {
+        let constants = [
+            &*SUPERFLIP,
+            &*CHECKERBOARD,
+            &*CUBE_IN_CUBE,
+            &*CUBE_IN_CUBE_IN_CUBE,
+            &*SIX_SPOT,
+            &*FOUR_SPOT,
+            &*TETRIS,
+            &*ANACONDA,
+        ];
+        let facelets = [
+            &*SUPERFLIP_FACELETS,
+            &*CHECKERBOARD_FACELETS,
+            &*CUBE_IN_CUBE_FACELETS,
+            &*CUBE_IN_CUBE_IN_CUBE_FACELETS,
+            &*SIX_SPOT_FACELETS,
+            &*FOUR_SPOT_FACELETS,
+            &*TETRIS_FACELETS,
+            &*ANACONDA_FACELETS,
+        ];
+        for ((pattern, &cube), &faces) in
+            patterns().iter().zip(constants.iter()).zip(facelets.iter())
+        {
+            assert_eq!(pattern.cube(), *cube, "{}", pattern.name);
+            assert_eq!(pattern.facelets, faces.to_string(), "{}", pattern.name);
+        }
+        assert_eq!(
+            Some("Tetris"),
+            matching_pattern(&TETRIS).map(|pattern| pattern.name)
+        );
+    }
+
+    #[test]
+    fn symmetries_map_moves_consistently() {
+        let moves = "R U' F2 L D B' R2".parse::<MoveSequence>().unwrap().0;
+
+        assert_eq!(48, SYMMETRIES.len());
+        for symmetry in SYMMETRIES.iter() {
+            let mapped: Vec<Move> = moves.iter().map(|m| symmetry.apply_move(m)).collect();
+
+            assert_eq!(
+                symmetry.apply(&FaceletCube::default().apply_moves(&moves)),
+                FaceletCube::default().apply_moves(&mapped)
+            );
+        }
+    }
+
+    #[test]
+    fn recognises_patterns_held_differently() {
+        let cube = |sequence: &str| {
+            CubieCube::default().apply_moves(&sequence.parse::<MoveSequence>().unwrap().0)
+        };
+
+        assert_eq!(
+            Some("Six spot"),
+            matching_pattern(&cube("R L' F B' U D' R L'")).map(|pattern| pattern.name)
+        );
+        assert_eq!(
+            Some("Checkerboard"),
+            matching_pattern(&cube("R2 L2 U2 D2 F2 B2")).map(|pattern| pattern.name)
+        );
+        assert_eq!(
+            Some("Anaconda"),
+            matching_pattern(&cube("R' U' B U L' R B' L F' B D' L' D F"))
+                .map(|pattern| pattern.name)
+        );
+        assert_eq!(None, matching_pattern(&cube("R U R' U'")));
+        assert_eq!(None, matching_pattern(&CubieCube::default()));
+    }
+
+    #[test]
+    fn solver_sequences_make_the_pattern() {
+        let pattern = find_pattern("Cube in cube").unwrap();
+        let sequence = pattern.solver_sequence();
+
+        assert!(pattern.matches(&CubieCube::default().apply_moves(&sequence)));
+        assert_eq!(pattern.cube(), CubieCube::default().apply_moves(&sequence));
+    }
+}
@@ -0,0 +1,398 @@
+use lazy_static::lazy_static;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::cube::Cube;
+use crate::cubie_cube::CubieCube;
+use crate::moves::Move;
+use crate::pochmann_solver::{
+    get_permitted_moves, permutation_rank, to_pochmann_cube, Phase, PochmannCube,
+};
+
+const MAGIC: &[u8; 4] = b"RCPT";
+const VERSION: u8 = 1;
+const UNVISITED: u8 = 3;
+
+#[derive(Clone, PartialEq, Eq)]
+struct PhaseTable {
+    entries: Vec<u8>,
+    len: usize,
+}
+
+impl PhaseTable {
+    fn unvisited(len: usize) -> Self {
+        PhaseTable {
+            entries: vec![0xff; len.div_ceil(4)],
+            len,
+        }
+    }
+
+    fn get(&self, coordinate: usize) -> u8 {
+        (self.entries[coordinate / 4] >> (2 * (coordinate % 4))) & 3
+    }
+
+    fn set(&mut self, coordinate: usize, value: u8) {
+        let shift = 2 * (coordinate % 4);
+        self.entries[coordinate / 4] =
+            (self.entries[coordinate / 4] & !(3 << shift)) | value << shift;
+    }
+}
+
+/// Distance tables for each of the four Thistlethwaite phases, indexed by a dense coordinate
+/// of the phase's coset.
+///
+/// Each entry holds the distance to the phase goal modulo three (packed four to a byte), which
+/// is enough to always pick a move one step closer to the goal, as neighbouring cosets are at
+/// most one move apart.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PhaseTables {
+    tables: Vec<PhaseTable>,
+}
+
+impl PhaseTables {
+    /// Builds every phase table by a breadth-first search outwards from the solved cube.
+    pub fn generate() -> Self {
+        PhaseTables {
+            tables: Phase::iterator().map(generate_table).collect(),
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < 5 || &bytes[0..4] != MAGIC {
+            return Err("Invalid phase tables");
+        }
+        if bytes[4] != VERSION {
+            return Err("Unsupported phase tables version");
+        }
+
+        let mut rest = &bytes[5..];
+        let mut tables = vec![];
+
+        for phase in Phase::iterator() {
+            if rest.len() < 4 {
+                return Err("Invalid phase tables");
+            }
+            let mut len = [0; 4];
+            len.copy_from_slice(&rest[0..4]);
+            let len = u32::from_le_bytes(len) as usize;
+            let size = len.div_ceil(4);
+
+            if len != coordinate_size(phase) || rest.len() < 4 + size {
+                return Err("Invalid phase tables");
+            }
+
+            tables.push(PhaseTable {
+                entries: rest[4..4 + size].to_vec(),
+                len,
+            });
+            rest = &rest[4 + size..];
+        }
+
+        if !rest.is_empty() {
+            return Err("Invalid phase tables");
+        }
+
+        Ok(PhaseTables { tables })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        for table in self.tables.iter() {
+            bytes.extend_from_slice(&(table.len as u32).to_le_bytes());
+            bytes.extend_from_slice(&table.entries);
+        }
+        bytes
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Greedily walks down the phase's distance table, returning `None` if the cube does not
+    /// belong to the phase's starting subgroup.
+    pub(crate) fn descend(&self, phase: Phase, cube: CubieCube) -> Option<Vec<Move>> {
+        let table = &self.tables[phase as usize];
+        let permitted_moves = get_permitted_moves(phase);
+        let goal = coordinate(phase, CubieCube::default())?;
+
+        let mut moves = vec![];
+        let mut state = cube;
+        let mut current = coordinate(phase, state)?;
+
+        while current != goal {
+            let distance = table.get(current);
+            if distance == UNVISITED {
+                return None;
+            }
+
+            let (action, next_state, next) = permitted_moves
+                .iter()
+                .map(|action| (*action, state.apply_move(action)))
+                .filter_map(|(action, next_state)| {
+                    coordinate(phase, next_state).map(|next| (action, next_state, next))
+                })
+                .find(|&(_, _, next)| table.get(next) == (distance + 2) % 3)?;
+
+            moves.push(action);
+            state = next_state;
+            current = next;
+        }
+
+        Some(moves)
+    }
+}
+
+lazy_static! {
+    static ref INSTALLED: RwLock<Option<Arc<PhaseTables>>> = RwLock::new(embedded());
+}
+
+#[cfg(feature = "embedded-tables")]
+fn embedded() -> Option<Arc<PhaseTables>> {
+    let bytes = include_bytes!("../tables/phase_tables.bin");
+    Some(Arc::new(
+        PhaseTables::from_bytes(bytes).expect("Embedded phase tables are invalid"),
+    ))
+}
+
+#[cfg(not(feature = "embedded-tables"))]
+fn embedded() -> Option<Arc<PhaseTables>> {
+    None
+}
+
+/// Uses the given tables for all subsequent solves, replacing any embedded tables.
+pub fn install_phase_tables(tables: PhaseTables) {
+    *INSTALLED.write().unwrap() = Some(Arc::new(tables));
+}
+
+pub(crate) fn installed_phase_tables() -> Option<Arc<PhaseTables>> {
+    INSTALLED.read().unwrap().clone()
+}
+
+fn generate_table(phase: Phase) -> PhaseTable {
+    let permitted_moves = get_permitted_moves(phase);
+    let mut table = PhaseTable::unvisited(coordinate_size(phase));
+    let mut frontier = vec![CubieCube::default()];
+    let mut depth = 0;
+
+    table.set(coordinate(phase, CubieCube::default()).unwrap(), 0);
+
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next_frontier = vec![];
+
+        for state in frontier {
+            for action in permitted_moves.iter() {
+                let next_state = state.apply_move(action);
+                let next = coordinate(phase, next_state).unwrap();
+                if table.get(next) == UNVISITED {
+                    table.set(next, depth % 3);
+                    next_frontier.push(next_state);
+                }
+            }
+        }
+
+        frontier = next_frontier;
+    }
+
+    table
+}
+
+fn coordinate_size(phase: Phase) -> usize {
+    match phase {
+        Phase::One => 1 << 12,
+        Phase::Two => 2187 * 495,
+        Phase::Three => 70 * 2520 * 2,
+        Phase::Four => 96 * 24 * 24 * 24,
+    }
+}
+
+/// Dense index of the phase coset the cube is within, following the same cosets as the
+/// solver's phase ids; `None` if the cube lies outside the phase's starting subgroup.
+fn coordinate(phase: Phase, cube: CubieCube) -> Option<usize> {
+    let pc = to_pochmann_cube(cube);
+
+    match phase {
+        Phase::One => Some(
+            pc[20..32]
+                .iter()
+                .enumerate()
+                .fold(0, |coord, (idx, &eo)| coord | (eo as usize) << idx),
+        ),
+        Phase::Two => {
+            let twist = pc[32..39]
+                .iter()
+                .fold(0, |coord, &co| coord * 3 + co as usize);
+            let slice = combination_rank(pc[0..12].iter().map(|&edge| edge > 7));
+            Some(twist * 495 + slice)
+        }
+        Phase::Three => {
+            if pc[8..12].iter().any(|&edge| edge < 8) {
+                return None;
+            }
+            let edges = combination_rank(pc[0..8].iter().map(|&edge| edge & 1 == 0));
+            let corners = corner_pair_rank(&pc);
+            let parity = (12..20)
+                .flat_map(|i| (i + 1..20).map(move |j| (i, j)))
+                .filter(|&(i, j)| pc[i] > pc[j])
+                .count()
+                % 2;
+            Some((edges * 2520 + corners) * 2 + parity)
+        }
+        Phase::Four => {
+            let tetrads = CORNER_TETRAD_INDEX[corner_tetrad_rank(&pc)?]?;
+            let mut slices = 0;
+            for slice in [[0, 2, 4, 6], [1, 3, 5, 7], [8, 9, 10, 11]].iter() {
+                let edges: Vec<u32> = slice.iter().map(|&idx| pc[idx]).collect();
+                if !edges.iter().all(|&edge| slice.contains(&(edge as usize))) {
+                    return None;
+                }
+                slices = slices * 24 + permutation_rank(&edges) as usize;
+            }
+            Some(tetrads as usize * 13824 + slices)
+        }
+    }
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// Colexicographic rank of the set of chosen positions.
+fn combination_rank<I: Iterator<Item = bool>>(chosen: I) -> usize {
+    chosen
+        .enumerate()
+        .filter(|&(_, is_chosen)| is_chosen)
+        .enumerate()
+        .map(|(k, (position, _))| binomial(position, k + 1))
+        .sum()
+}
+
+/// Rank of the arrangement of the four corner pairs Pochmann's third phase groups corners by.
+fn corner_pair_rank(pc: &PochmannCube) -> usize {
+    let mut pairs: Vec<u32> = pc[12..20]
+        .iter()
+        .map(|&corner| match (corner - 12) & 5 {
+            0 => 0,
+            1 => 1,
+            4 => 2,
+            _ => 3,
+        })
+        .collect();
+
+    let mut rank = 0;
+    for (pair, radix) in [(0, 28), (1, 15), (2, 6)].iter() {
+        rank = rank * radix + combination_rank(pairs.iter().map(|p| p == pair));
+        pairs.retain(|p| p != pair);
+    }
+    rank
+}
+
+/// Rank of the corner permutation within each of the two tetrads half turns keep corners in;
+/// `None` if a corner has left its tetrad.
+fn corner_tetrad_rank(pc: &PochmannCube) -> Option<usize> {
+    let tetrad = |positions: [usize; 4]| {
+        let corners: Vec<u32> = positions.iter().map(|&idx| pc[12 + idx]).collect();
+        if corners
+            .iter()
+            .all(|&corner| positions.contains(&(corner as usize - 12)))
+        {
+            Some(permutation_rank(&corners) as usize)
+        } else {
+            None
+        }
+    };
+
+    Some(tetrad([0, 2, 5, 7])? * 24 + tetrad([1, 3, 4, 6])?)
+}
+
+lazy_static! {
+    /// Only 96 of the 576 tetrad arrangements are reachable using half turns, so these are
+    /// mapped to a dense index.
+    static ref CORNER_TETRAD_INDEX: [Option<u8>; 576] = {
+        let permitted_moves = get_permitted_moves(Phase::Four);
+        let mut index = [None; 576];
+        let mut frontier = vec![CubieCube::default()];
+        let mut total = 0;
+
+        index[corner_tetrad_rank(&to_pochmann_cube(CubieCube::default())).unwrap()] = Some(0);
+
+        while let Some(state) = frontier.pop() {
+            for action in permitted_moves.iter() {
+                let next_state = state.apply_move(action);
+                let rank = corner_tetrad_rank(&to_pochmann_cube(next_state)).unwrap();
+                if index[rank].is_none() {
+                    total += 1;
+                    index[rank] = Some(total);
+                    frontier.push(next_state);
+                }
+            }
+        }
+
+        index
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "embedded-tables")]
+    fn embedded_tables_match_generated_phase_one_table() {
+        let tables = installed_phase_tables().unwrap();
+        assert!(tables.tables[Phase::One as usize] == generate_table(Phase::One));
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-tables")]
+    fn bytes_round_trip() {
+        let tables = installed_phase_tables().unwrap();
+        assert!(*tables == PhaseTables::from_bytes(&tables.to_bytes()).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-tables")]
+    fn rejects_truncated_bytes() {
+        let bytes = installed_phase_tables().unwrap().to_bytes();
+        assert!(PhaseTables::from_bytes(&bytes[0..bytes.len() - 1]).is_err());
+        assert!(PhaseTables::from_bytes(b"RCPT").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-tables")]
+    fn descent_reaches_each_phase_goal() {
+        let tables = installed_phase_tables().unwrap();
+        let mut cube = CubieCube::random(100);
+
+        for phase in Phase::iterator() {
+            cube = cube.apply_moves(&tables.descend(phase, cube).unwrap());
+            assert_eq!(
+                coordinate(phase, CubieCube::default()),
+                coordinate(phase, cube)
+            );
+        }
+
+        assert_eq!(CubieCube::default(), cube);
+    }
+
+    #[test]
+    fn combination_rank_is_dense() {
+        let mut ranks: Vec<usize> = (0..1 << 8)
+            .filter(|mask: &u32| mask.count_ones() == 4)
+            .map(|mask| combination_rank((0..8).map(|bit| mask & (1 << bit) != 0)))
+            .collect();
+        ranks.sort_unstable();
+        assert_eq!((0..70).collect::<Vec<_>>(), ranks);
+    }
+}
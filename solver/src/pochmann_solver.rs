@@ -1,14 +1,19 @@
+use serde::Serialize;
 use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::sync::Arc;
 
 use crate::cube::Cube;
 use crate::cubie_cube::Corner::*;
 use crate::cubie_cube::Edge::*;
 use crate::cubie_cube::{Corner, CubieCube, Edge};
+use crate::facelet_cube::FaceletCube;
 use crate::moves::Direction::*;
 use crate::moves::Position::*;
 use crate::moves::{Direction, Move};
+use crate::phase_tables::{installed_phase_tables, PhaseTables};
 
-type PochmannCube = [u32; 40];
+pub(crate) type PochmannCube = [u32; 40];
 
 fn to_pochmann_edge(e: Edge) -> u32 {
     match e {
@@ -40,8 +45,8 @@ fn to_pochmann_corner(c: Corner) -> u32 {
     }
 }
 
-fn to_pochmann_cube(cube: CubieCube) -> PochmannCube {
-    let mut pc = [0 as u32; 40];
+pub(crate) fn to_pochmann_cube(cube: CubieCube) -> PochmannCube {
+    let mut pc = [0_u32; 40];
 
     pc[0] = to_pochmann_edge(cube.ep[UF as usize]);
     pc[1] = to_pochmann_edge(cube.ep[UR as usize]);
@@ -90,46 +95,60 @@ fn to_pochmann_cube(cube: CubieCube) -> PochmannCube {
     pc
 }
 
-fn to_phase_id(phase: Phase, cube: CubieCube) -> PochmannCube {
+/// A phase id packed into a single word; states with equal ids belong to the same coset of
+/// the phase's target subgroup.
+type PhaseId = u64;
+
+/// Index of a node within a search's parent-pointer tree.
+type NodeId = u32;
+
+fn to_phase_id(phase: Phase, cube: CubieCube) -> PhaseId {
     let pc = to_pochmann_cube(cube);
 
     match phase {
-        Phase::One => {
-            let mut r = [0 as u32; 40];
-            for idx in 0..12 {
-                r[idx] = pc[20 + idx] as u32;
-            }
-            r
-        }
+        Phase::One => pc[20..32]
+            .iter()
+            .enumerate()
+            .fold(0, |id, (idx, &eo)| id | (eo as PhaseId) << idx),
         Phase::Two => {
-            let mut r = [0 as u32; 40];
-            for idx in 0..8 {
-                r[idx] = pc[32 + idx] as u32;
-            }
-            for idx in 0..12 {
-                r[0] |= (pc[idx] as u32 / 8) << idx;
+            let mut id = pc[32..40]
+                .iter()
+                .enumerate()
+                .fold(0, |id, (idx, &co)| id | (co as PhaseId) << (2 * idx));
+            for (idx, &edge) in pc[0..12].iter().enumerate() {
+                id |= ((edge / 8) as PhaseId) << (16 + idx);
             }
-            r
+            id
         }
         Phase::Three => {
-            let mut r = [0 as u32; 40];
-            for idx in 0..12 {
-                r[0] |= (if pc[idx] > 7 { 2 } else { pc[idx] as u32 & 1 }) << (2 * idx);
+            let mut id = 0;
+            for (idx, &edge) in pc[0..12].iter().enumerate() {
+                id |= (if edge > 7 { 2 } else { edge as PhaseId & 1 }) << (2 * idx);
             }
-            for idx in 0..8 {
-                r[1] |= (((pc[idx + 12] as u32) - 12) & 5) << (3 * idx);
+            for (idx, &corner) in pc[12..20].iter().enumerate() {
+                id |= (((corner - 12) & 5) as PhaseId) << (24 + 3 * idx);
             }
             for i in 12..20 {
                 for j in i + 1..20 {
-                    r[2] ^= if pc[i] > pc[j] { 1 } else { 0 }
+                    id ^= if pc[i] > pc[j] { 1 << 48 } else { 0 }
                 }
             }
-            r
+            id
         }
-        Phase::Four => pc,
+        // Every orientation is solved within the final subgroup, so the permutations alone
+        // identify the state.
+        Phase::Four => permutation_rank(&pc[0..12]) << 16 | permutation_rank(&pc[12..20]),
     }
 }
 
+/// Lexicographic rank of a permutation of distinct values (at most 20! fits within a `u64`).
+pub(crate) fn permutation_rank(values: &[u32]) -> u64 {
+    values.iter().enumerate().fold(0, |rank, (idx, &value)| {
+        let smaller_after = values[idx + 1..].iter().filter(|&&v| v < value).count() as u64;
+        rank * (values.len() - idx) as u64 + smaller_after
+    })
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 enum QueueDirection {
     Forward,
@@ -145,29 +164,30 @@ impl QueueDirection {
     }
 }
 
-fn build_move_seq(
-    history: HashMap<(PochmannCube, QueueDirection), Vec<Move>>,
-    state: &PochmannCube,
-) -> Vec<Move> {
-    let mut moves = history
-        .get(&(*state, QueueDirection::Forward))
-        .unwrap()
-        .clone();
-
-    let mut ending: Vec<Move> = history
-        .get(&(*state, QueueDirection::Backward))
-        .unwrap()
-        .iter()
-        .map(|m| m.inverse())
-        .collect::<Vec<_>>();
-    ending.reverse();
+/// A visited state, recorded as the single move which first reached it from its parent.
+struct SearchNode {
+    parent: NodeId,
+    action: Option<Move>,
+}
+
+fn path_to(nodes: &[SearchNode], mut node: NodeId) -> Vec<Move> {
+    let mut moves = vec![];
+    while let Some(action) = nodes[node as usize].action {
+        moves.push(action);
+        node = nodes[node as usize].parent;
+    }
+    moves.reverse();
+    moves
+}
 
-    moves.append(&mut ending);
+fn build_move_seq(nodes: &[SearchNode], forward: NodeId, backward: NodeId) -> Vec<Move> {
+    let mut moves = path_to(nodes, forward);
+    moves.extend(path_to(nodes, backward).iter().rev());
     moves
 }
 
 #[derive(Copy, Clone)]
-enum Phase {
+pub(crate) enum Phase {
     One,
     Two,
     Three,
@@ -175,14 +195,33 @@ enum Phase {
 }
 
 impl Phase {
-    fn iterator() -> impl Iterator<Item = Phase> {
+    pub(crate) fn iterator() -> impl Iterator<Item = Phase> {
         [Phase::One, Phase::Two, Phase::Three, Phase::Four]
             .iter()
             .copied()
     }
+
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Phase::One => "Orient edges",
+            Phase::Two => "Orient corners and place middle layer edges",
+            Phase::Three => "Place corners and edges in their half turn orbits",
+            Phase::Four => "Solve using half turns",
+        }
+    }
+
+    /// The subgroup the cube is brought into by the end of the phase.
+    pub(crate) fn subgroup(self) -> &'static str {
+        match self {
+            Phase::One => "<U, D, L, R, F2, B2>",
+            Phase::Two => "<U, D, L2, R2, F2, B2>",
+            Phase::Three => "<U2, D2, L2, R2, F2, B2>",
+            Phase::Four => "Solved",
+        }
+    }
 }
 
-fn get_permitted_moves(phase: Phase) -> Vec<Move> {
+pub(crate) fn get_permitted_moves(phase: Phase) -> Vec<Move> {
     match phase {
         Phase::One => vec![
             Move(Up, Normal),
@@ -243,63 +282,281 @@ fn get_permitted_moves(phase: Phase) -> Vec<Move> {
     }
 }
 
-fn bidirection_bfs(phase: Phase, current_cube: CubieCube) -> Vec<Move> {
-    let current_id = to_phase_id(phase, current_cube);
-    let goal_id = to_phase_id(phase, CubieCube::default());
-
-    if current_id == goal_id {
-        return vec![];
-    };
+/// The outcome of advancing a search.
+pub(crate) enum SearchStatus {
+    /// The search was paused with states left to visit.
+    Pending,
+    Found(Vec<Move>),
+    /// Every reachable state has been visited.
+    Exhausted,
+}
 
-    let mut history: HashMap<(PochmannCube, QueueDirection), Vec<Move>> = HashMap::new();
-    let mut queue: VecDeque<(CubieCube, QueueDirection)> = VecDeque::new();
+/// Meets in the middle between the cube and the solved state using only the given moves, where
+/// states with equal ids are considered the same. The search advances a bounded number of states
+/// at a time, see `advance`.
+pub(crate) struct BidirectionalSearch<K, F> {
+    permitted_moves: Vec<Move>,
+    to_id: F,
+    nodes: Vec<SearchNode>,
+    visited: HashMap<(K, QueueDirection), NodeId>,
+    queue: VecDeque<(CubieCube, NodeId, QueueDirection)>,
+    expanded: usize,
+    found: Option<Vec<Move>>,
+}
 
-    history.insert((current_id, QueueDirection::Forward), vec![]);
-    history.insert((goal_id, QueueDirection::Backward), vec![]);
+impl<K, F> BidirectionalSearch<K, F>
+where
+    K: Copy + Eq + Hash,
+    F: Fn(CubieCube) -> K,
+{
+    pub(crate) fn new(current_cube: CubieCube, permitted_moves: &[Move], to_id: F) -> Self {
+        let current_id = to_id(current_cube);
+        let goal_id = to_id(CubieCube::default());
+
+        let mut search = BidirectionalSearch {
+            permitted_moves: permitted_moves.to_vec(),
+            to_id,
+            nodes: vec![],
+            visited: HashMap::new(),
+            queue: VecDeque::new(),
+            expanded: 0,
+            found: None,
+        };
+
+        if current_id == goal_id {
+            search.found = Some(vec![]);
+            return search;
+        };
+
+        for &(cube, id, direction) in [
+            (current_cube, current_id, QueueDirection::Forward),
+            (CubieCube::default(), goal_id, QueueDirection::Backward),
+        ]
+        .iter()
+        {
+            let node = search.nodes.len() as NodeId;
+            search.nodes.push(SearchNode {
+                parent: node,
+                action: None,
+            });
+            search.visited.insert((id, direction), node);
+            search.queue.push_back((cube, node, direction));
+        }
 
-    queue.push_back((current_cube, QueueDirection::Forward));
-    queue.push_back((CubieCube::default(), QueueDirection::Backward));
+        search
+    }
 
-    while let Some((state, direction)) = queue.pop_front() {
-        for action in get_permitted_moves(phase) {
-            let next_state = state.apply_move(&action);
-            let next_id = to_phase_id(phase, next_state);
+    /// The number of states whose moves have been explored so far.
+    pub(crate) fn expanded(&self) -> usize {
+        self.expanded
+    }
 
-            if history.contains_key(&(next_id, direction)) {
-                continue;
-            }
+    /// Expands at most `budget` queued states before pausing.
+    pub(crate) fn advance(&mut self, budget: usize) -> SearchStatus {
+        if let Some(moves) = self.found.take() {
+            return SearchStatus::Found(moves);
+        }
 
-            let mut state_moves: Vec<Move> = history
-                .get(&(to_phase_id(phase, state), direction))
-                .unwrap()
-                .clone();
-            state_moves.push(action);
+        for _ in 0..budget {
+            let (state, node, direction) = match self.queue.pop_front() {
+                Some(entry) => entry,
+                None => return SearchStatus::Exhausted,
+            };
+            self.expanded += 1;
+
+            for action in self.permitted_moves.iter() {
+                // The backward search undoes moves, so that its path is replayed as recorded.
+                let next_state = match direction {
+                    QueueDirection::Forward => state.apply_move(action),
+                    QueueDirection::Backward => state.apply_move(&action.inverse()),
+                };
+                let next_id = (self.to_id)(next_state);
+
+                if self.visited.contains_key(&(next_id, direction)) {
+                    continue;
+                }
 
-            history.insert((next_id, direction), state_moves);
+                let next_node = self.nodes.len() as NodeId;
+                self.nodes.push(SearchNode {
+                    parent: node,
+                    action: Some(*action),
+                });
+                self.visited.insert((next_id, direction), next_node);
+
+                if let Some(&other_node) = self.visited.get(&(next_id, direction.opposite())) {
+                    return SearchStatus::Found(match direction {
+                        QueueDirection::Forward => {
+                            build_move_seq(&self.nodes, next_node, other_node)
+                        }
+                        QueueDirection::Backward => {
+                            build_move_seq(&self.nodes, other_node, next_node)
+                        }
+                    });
+                }
 
-            if history.contains_key(&(next_id, direction.opposite())) {
-                return build_move_seq(history, &next_id);
+                self.queue.push_back((next_state, next_node, direction));
             }
+        }
 
-            queue.push_back((next_state, direction));
+        if self.queue.is_empty() {
+            SearchStatus::Exhausted
+        } else {
+            SearchStatus::Pending
         }
     }
+}
+
+type PhaseSearch = BidirectionalSearch<PhaseId, Box<dyn Fn(CubieCube) -> PhaseId>>;
 
-    vec![]
+fn phase_search(phase: Phase, cube: CubieCube) -> PhaseSearch {
+    BidirectionalSearch::new(
+        cube,
+        &get_permitted_moves(phase),
+        Box::new(move |cube| to_phase_id(phase, cube)),
+    )
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct PhaseSolution {
+    pub(crate) description: &'static str,
+    pub(crate) subgroup: &'static str,
+    pub(crate) moves: Vec<Move>,
+    /// The cube once the phase's moves have been applied.
+    pub(crate) cube: FaceletCube,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Solution {
+    pub(crate) phases: Vec<PhaseSolution>,
+    /// Every phase's moves, with moves of the same face across phase boundaries merged.
+    pub(crate) moves: Vec<Move>,
+    /// The indices of the phases each merged move originates from.
+    pub(crate) move_phases: Vec<Vec<usize>>,
 }
 
 pub(crate) fn solve(cube: &CubieCube) -> Option<Vec<Move>> {
-    let mut solution = vec![];
+    solve_in_phases(cube).map(|solution| solution.moves)
+}
 
-    for phase in Phase::iterator() {
-        let phase_moves = bidirection_bfs(phase, cube.apply_moves(&solution));
-        solution.extend(phase_moves);
+/// Solves each phase by descending the installed phase tables, falling back to a bidirectional
+/// search when none are available.
+pub(crate) fn solve_in_phases(cube: &CubieCube) -> Option<Solution> {
+    match IncrementalSolve::new(cube).step(usize::MAX) {
+        SolveStatus::Solved(solution) => Some(solution),
+        _ => None,
     }
+}
 
-    Some(simplify_multi_face_moves(&solution))
+/// The outcome of advancing an `IncrementalSolve`.
+pub(crate) enum SolveStatus {
+    /// Work remains, to be continued by another step.
+    Pending,
+    Solved(Solution),
+    Unsolvable,
 }
 
-fn simplify_multi_face_moves(solution: &Vec<Move>) -> Vec<Move> {
+/// Solves a cube phase by phase, a bounded amount of work at a time, so that callers can keep
+/// responsive (or give up) while a long search is under way.
+pub(crate) struct IncrementalSolve {
+    tables: Option<Arc<PhaseTables>>,
+    /// The cube as of the start of the phase being solved.
+    cube: CubieCube,
+    phases: Vec<PhaseSolution>,
+    search: Option<PhaseSearch>,
+}
+
+impl IncrementalSolve {
+    pub(crate) fn new(cube: &CubieCube) -> Self {
+        IncrementalSolve {
+            tables: installed_phase_tables(),
+            cube: *cube,
+            phases: vec![],
+            search: None,
+        }
+    }
+
+    /// The number of phases solved so far.
+    pub(crate) fn phases_solved(&self) -> usize {
+        self.phases.len()
+    }
+
+    /// The number of states explored by the current phase's search.
+    pub(crate) fn states_expanded(&self) -> usize {
+        self.search.as_ref().map_or(0, PhaseSearch::expanded)
+    }
+
+    /// Does at most `budget` units of work, where descending a phase's table costs one unit and
+    /// searching costs one unit per explored state.
+    pub(crate) fn step(&mut self, mut budget: usize) -> SolveStatus {
+        while budget > 0 {
+            let phase = match Phase::iterator().nth(self.phases.len()) {
+                Some(phase) => phase,
+                None => break,
+            };
+
+            let phase_moves = match self.tables {
+                Some(ref tables) => {
+                    budget -= 1;
+                    tables.descend(phase, self.cube)
+                }
+                None => {
+                    let cube = self.cube;
+                    let search = self.search.get_or_insert_with(|| phase_search(phase, cube));
+                    let expanded = search.expanded();
+                    let status = search.advance(budget);
+                    budget -= search.expanded() - expanded;
+                    match status {
+                        SearchStatus::Pending => return SolveStatus::Pending,
+                        SearchStatus::Found(moves) => Some(moves),
+                        SearchStatus::Exhausted => None,
+                    }
+                }
+            };
+
+            let phase_moves = match phase_moves {
+                Some(moves) => moves,
+                None => return SolveStatus::Unsolvable,
+            };
+            self.search = None;
+            self.cube = self.cube.apply_moves(&phase_moves);
+            self.phases.push(PhaseSolution {
+                description: phase.description(),
+                subgroup: phase.subgroup(),
+                moves: phase_moves,
+                cube: FaceletCube::from(self.cube),
+            });
+        }
+
+        if self.phases.len() < Phase::iterator().count() {
+            return SolveStatus::Pending;
+        }
+        if self.cube != CubieCube::default() {
+            return SolveStatus::Unsolvable;
+        }
+
+        let phases = self.phases.clone();
+        let (moves, move_phases) = simplify_multi_face_moves(
+            &phases
+                .iter()
+                .enumerate()
+                .flat_map(|(idx, phase)| phase.moves.iter().map(move |&action| (action, idx)))
+                .collect::<Vec<_>>(),
+        )
+        .into_iter()
+        .unzip();
+
+        SolveStatus::Solved(Solution {
+            phases,
+            moves,
+            move_phases,
+        })
+    }
+}
+
+/// Merges consecutive moves of the same face, keeping track of the phases each merged move
+/// came from.
+fn simplify_multi_face_moves(solution: &[(Move, usize)]) -> Vec<(Move, Vec<usize>)> {
     fn to_quarter_turns(d: Direction) -> u8 {
         match d {
             Normal => 1,
@@ -317,19 +574,22 @@ fn simplify_multi_face_moves(solution: &Vec<Move>) -> Vec<Move> {
         }
     }
 
-    let mut simplified_solution: Vec<Move> = vec![];
+    let mut simplified_solution: Vec<(Move, Vec<usize>)> = vec![];
 
-    for &Move(position, direction) in solution.iter() {
+    for &(Move(position, direction), phase) in solution.iter() {
         match simplified_solution.last() {
-            Some(&Move(last_position, last_direction)) if position == last_position => {
-                simplified_solution.pop();
+            Some(&(Move(last_position, last_direction), _)) if position == last_position => {
+                let (_, mut phases) = simplified_solution.pop().unwrap();
+                if !phases.contains(&phase) {
+                    phases.push(phase);
+                }
                 if let Some(new_direction) =
                     to_direction(to_quarter_turns(direction) + to_quarter_turns(last_direction))
                 {
-                    simplified_solution.push(Move(position, new_direction));
+                    simplified_solution.push((Move(position, new_direction), phases));
                 }
             }
-            _ => simplified_solution.push(Move(position, direction)),
+            _ => simplified_solution.push((Move(position, direction), vec![phase])),
         }
     }
 
@@ -339,6 +599,7 @@ fn simplify_multi_face_moves(solution: &Vec<Move>) -> Vec<Move> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::moves::MoveSequence;
     use crate::CubieCube;
     use crate::FaceletCube;
 
@@ -376,7 +637,109 @@ mod tests {
         assert_eq!(FaceletCube::default(), cube.apply_moves(&solution));
     }
 
-    fn assert_no_multi_face_turns(solution: &Vec<Move>) {
+    #[test]
+    fn solution_is_annotated_with_phases() {
+        let cube = CubieCube::random(100);
+        let solution = solve_in_phases(&cube).unwrap();
+
+        let mut phase_cube = cube;
+        for phase in solution.phases.iter() {
+            phase_cube = phase_cube.apply_moves(&phase.moves);
+            assert_eq!(phase.cube, FaceletCube::from(phase_cube));
+        }
+        assert_eq!(FaceletCube::default(), solution.phases[3].cube);
+
+        assert_eq!(solution.moves.len(), solution.move_phases.len());
+        assert_eq!(CubieCube::default(), cube.apply_moves(&solution.moves));
+    }
+
+    #[test]
+    fn merged_moves_keep_their_phases() {
+        let merged = simplify_multi_face_moves(&[
+            (Move(Up, Normal), 0),
+            (Move(Right, Normal), 0),
+            (Move(Right, Normal), 1),
+            (Move(Left, Half), 1),
+            (Move(Left, Half), 2),
+            (Move(Front, Half), 3),
+        ]);
+
+        assert_eq!(
+            vec![
+                (Move(Up, Normal), vec![0]),
+                (Move(Right, Half), vec![0, 1]),
+                (Move(Front, Half), vec![3]),
+            ],
+            merged
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-tables")]
+    fn unsolvable_cube_has_no_solution() {
+        let twisted_corner = "WWWWWWWWGWRRRRRRRRGGRGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB"
+            .parse::<FaceletCube>()
+            .unwrap();
+
+        assert_eq!(None, solve(&CubieCube::from(twisted_corner)));
+    }
+
+    #[test]
+    fn each_phase_reaches_its_goal_subgroup() {
+        let mut cube = CubieCube::random(100);
+
+        for phase in Phase::iterator() {
+            match phase_search(phase, cube).advance(usize::MAX) {
+                SearchStatus::Found(moves) => cube = cube.apply_moves(&moves),
+                _ => panic!("Phase has no solution"),
+            }
+            assert_eq!(
+                to_phase_id(phase, CubieCube::default()),
+                to_phase_id(phase, cube)
+            );
+        }
+    }
+
+    #[test]
+    fn incremental_search_pauses_within_its_budget() {
+        // A random cube may be solved within a single budget, so one needing several is used.
+        let scramble = "R U F' L2 D B' R2 U' F D2 L B"
+            .parse::<MoveSequence>()
+            .unwrap()
+            .0;
+        let cube = CubieCube::default().apply_moves(&scramble);
+        let mut solve = IncrementalSolve {
+            tables: None,
+            ..IncrementalSolve::new(&cube)
+        };
+
+        let mut pauses = 0;
+        let solution = loop {
+            match solve.step(1000) {
+                SolveStatus::Pending => {
+                    // A phase may be finished with the last of the budget, before the next begins.
+                    assert!(solve.states_expanded() > 0 || solve.phases_solved() > 0);
+                    pauses += 1;
+                }
+                SolveStatus::Solved(solution) => break solution,
+                SolveStatus::Unsolvable => panic!("Cube has no solution"),
+            }
+        };
+
+        assert!(pauses > 0);
+        assert_eq!(4, solve.phases_solved());
+        assert_eq!(CubieCube::default(), cube.apply_moves(&solution.moves));
+    }
+
+    #[test]
+    fn permutation_rank_is_lexicographic() {
+        assert_eq!(0, permutation_rank(&[0, 1, 2, 3]));
+        assert_eq!(1, permutation_rank(&[0, 1, 3, 2]));
+        assert_eq!(23, permutation_rank(&[3, 2, 1, 0]));
+        assert_eq!(40319, permutation_rank(&[19, 18, 17, 16, 15, 14, 13, 12]));
+    }
+
+    fn assert_no_multi_face_turns(solution: &[Move]) {
         let has_multi_face_turn = solution
             .iter()
             .zip(solution.iter().skip(1))
@@ -0,0 +1,401 @@
+//! Python bindings, built as the `rubik_cube_solver` extension module (see `pyproject.toml`).
+
+// `#[pymethods]` take `self` by reference and wrap their results in conversions clippy sees through.
+#![allow(clippy::wrong_self_convention, clippy::useless_conversion)]
+
+use pyo3::basic::CompareOp;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::cube::Cube;
+use crate::cubie_cube::CubieCube;
+use crate::facelet_cube::FaceletCube;
+use crate::moves::{Direction, Move, MoveSequence};
+use crate::pochmann_solver::solve;
+use crate::scramble::random_scramble;
+use crate::subgroup::solve_with_moves;
+
+fn value_error(err: &'static str) -> PyErr {
+    PyValueError::new_err(err)
+}
+
+/// Parses a cube's JSON, rejecting cubes which can not be reached from solved.
+fn parse_cubie_cube(json: &str) -> PyResult<CubieCube> {
+    let cube: CubieCube = json.parse().map_err(value_error)?;
+    cube.verify().map_err(value_error)?;
+    Ok(cube)
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The least common multiple of the lengths of the cycles `permutation` moves pieces around in,
+/// each multiplied by `twists` when its pieces' orientations do not add up to zero.
+fn cycles_order(permutation: &[usize], orientations: &[u8], twists: u8) -> u32 {
+    let mut visited = vec![false; permutation.len()];
+    let mut order = 1;
+    for start in 0..permutation.len() {
+        let (mut idx, mut length, mut twist) = (start, 0, 0);
+        while !visited[idx] {
+            visited[idx] = true;
+            twist += orientations[idx];
+            length += 1;
+            idx = permutation[idx];
+        }
+        if length > 0 {
+            let length = if twist % twists == 0 {
+                length
+            } else {
+                length * u32::from(twists)
+            };
+            order = order / gcd(order, length) * length;
+        }
+    }
+    order
+}
+
+fn hash(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn compare<T: PartialEq>(a: &T, b: &T, op: CompareOp, py: Python) -> PyObject {
+    match op {
+        CompareOp::Eq => (a == b).into_py(py),
+        CompareOp::Ne => (a != b).into_py(py),
+        _ => py.NotImplemented(),
+    }
+}
+
+/// Accepts either a string of space separated moves (e.g. "R U R' U'") or a sequence of `Move`s
+/// and move strings.
+fn extract_moves(moves: &Bound<'_, PyAny>) -> PyResult<Vec<Move>> {
+    if let Ok(moves) = moves.extract::<String>() {
+        return moves
+            .parse::<MoveSequence>()
+            .map(|sequence| sequence.0)
+            .map_err(value_error);
+    }
+
+    moves
+        .iter()?
+        .map(|action| {
+            let action = action?;
+            if let Ok(action) = action.extract::<PyMove>() {
+                Ok(action.0)
+            } else if let Ok(action) = action.extract::<String>() {
+                action.parse().map_err(value_error)
+            } else {
+                Err(PyTypeError::new_err("Moves must be given as Move or str"))
+            }
+        })
+        .collect()
+}
+
+fn to_py_moves(moves: Vec<Move>) -> Vec<PyMove> {
+    moves.into_iter().map(PyMove).collect()
+}
+
+fn solve_cubie_cube(cube: &CubieCube) -> PyResult<Vec<PyMove>> {
+    cube.verify().map_err(value_error)?;
+    solve(cube)
+        .map(to_py_moves)
+        .ok_or_else(|| value_error("Cube has no solution"))
+}
+
+fn solve_cubie_cube_with(
+    cube: &CubieCube,
+    permitted_moves: &Bound<'_, PyAny>,
+) -> PyResult<Vec<PyMove>> {
+    solve_with_moves(cube, &extract_moves(permitted_moves)?)
+        .map(to_py_moves)
+        .map_err(value_error)
+}
+
+#[pyclass(name = "Move", module = "rubik_cube_solver")]
+#[derive(Copy, Clone)]
+struct PyMove(Move);
+
+#[pymethods]
+impl PyMove {
+    #[new]
+    fn new(notation: &str) -> PyResult<Self> {
+        notation.parse().map(PyMove).map_err(value_error)
+    }
+
+    /// The face turned, as its letter (e.g. "R").
+    #[getter]
+    fn face(&self) -> String {
+        self.0.to_string()[..1].to_string()
+    }
+
+    /// The number of clockwise quarter turns made; 1, 2 or -1 for anti-clockwise.
+    #[getter]
+    fn turns(&self) -> i8 {
+        match self.0 .1 {
+            Direction::Normal => 1,
+            Direction::Half => 2,
+            Direction::Prime => -1,
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        PyMove(self.0.inverse())
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Move('{}')", self.0)
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python) -> PyObject {
+        compare(&self.0, &other.0, op, py)
+    }
+
+    fn __hash__(&self) -> u64 {
+        hash(&self.0)
+    }
+
+    fn __getnewargs__(&self) -> (String,) {
+        (self.0.to_string(),)
+    }
+}
+
+#[pyclass(name = "CubieCube", module = "rubik_cube_solver")]
+#[derive(Copy, Clone)]
+struct PyCubieCube(CubieCube);
+
+#[pymethods]
+impl PyCubieCube {
+    /// The solved cube.
+    #[new]
+    fn new() -> Self {
+        PyCubieCube(CubieCube::default())
+    }
+
+    /// A cube scrambled by the given number of random moves.
+    #[staticmethod]
+    #[pyo3(signature = (moves = 100))]
+    fn random(moves: u8) -> Self {
+        PyCubieCube(CubieCube::random(moves))
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        parse_cubie_cube(json).map(PyCubieCube)
+    }
+
+    fn to_json(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn to_facelet_cube(&self) -> PyFaceletCube {
+        PyFaceletCube(FaceletCube::from(self.0))
+    }
+
+    #[getter]
+    fn cp(&self) -> Vec<u8> {
+        self.0.cp.iter().map(|&corner| corner as u8).collect()
+    }
+
+    #[getter]
+    fn co(&self) -> Vec<u8> {
+        self.0.co.to_vec()
+    }
+
+    #[getter]
+    fn ep(&self) -> Vec<u8> {
+        self.0.ep.iter().map(|&edge| edge as u8).collect()
+    }
+
+    #[getter]
+    fn eo(&self) -> Vec<u8> {
+        self.0.eo.to_vec()
+    }
+
+    fn apply(&self, moves: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Ok(PyCubieCube(self.0.apply_moves(&extract_moves(moves)?)))
+    }
+
+    fn is_solved(&self) -> bool {
+        self.0 == CubieCube::default()
+    }
+
+    /// Raises `ValueError` if the cube can not be reached from solved.
+    fn verify(&self) -> PyResult<()> {
+        self.0.verify().map_err(value_error)
+    }
+
+    fn solve(&self) -> PyResult<Vec<PyMove>> {
+        solve_cubie_cube(&self.0)
+    }
+
+    /// Finds a short solution using only the permitted face turns. Slice moves such as M are
+    /// not supported and raise `ValueError`.
+    fn solve_with(&self, permitted_moves: &Bound<'_, PyAny>) -> PyResult<Vec<PyMove>> {
+        solve_cubie_cube_with(&self.0, permitted_moves)
+    }
+
+    /// The cube reached by applying this cube's permutation and orientation changes followed by
+    /// `other`'s.
+    fn __mul__(&self, other: &Self) -> Self {
+        PyCubieCube(self.0.multiply(other.0))
+    }
+
+    /// Applies the cube to itself `exponent` times, with negative exponents applying its
+    /// inverse. The exponent is first reduced modulo the cube's order, so at most 1259 are made.
+    fn __pow__(&self, exponent: i64, _modulo: Option<&Bound<'_, PyAny>>) -> Self {
+        let turns = exponent.rem_euclid(i64::from(self.order()));
+        PyCubieCube((0..turns).fold(CubieCube::default(), |cube, _| cube.multiply(self.0)))
+    }
+
+    fn inverse(&self) -> Self {
+        PyCubieCube(self.0.inverse())
+    }
+
+    /// The number of times the cube must be applied to itself to return to solved, at most
+    /// 1260. Worked out from the cycles the cube moves its pieces around in.
+    fn order(&self) -> u32 {
+        let cp: Vec<usize> = self.0.cp.iter().map(|&corner| corner as usize).collect();
+        let ep: Vec<usize> = self.0.ep.iter().map(|&edge| edge as usize).collect();
+        let corners = cycles_order(&cp, &self.0.co, 3);
+        let edges = cycles_order(&ep, &self.0.eo, 2);
+        corners / gcd(corners, edges) * edges
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.0)
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python) -> PyObject {
+        compare(&self.0, &other.0, op, py)
+    }
+
+    fn __hash__(&self) -> u64 {
+        hash(&self.0)
+    }
+
+    fn __getstate__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __setstate__(&mut self, state: &str) -> PyResult<()> {
+        self.0 = parse_cubie_cube(state)?;
+        Ok(())
+    }
+}
+
+#[pyclass(name = "FaceletCube", module = "rubik_cube_solver")]
+#[derive(Copy, Clone)]
+struct PyFaceletCube(FaceletCube);
+
+#[pymethods]
+impl PyFaceletCube {
+    /// The cube described by the given facelets, or the solved cube when none are given.
+    #[new]
+    #[pyo3(signature = (facelets = None))]
+    fn new(facelets: Option<&str>) -> PyResult<Self> {
+        match facelets {
+            Some(facelets) => facelets.parse().map(PyFaceletCube).map_err(value_error),
+            None => Ok(PyFaceletCube(FaceletCube::default())),
+        }
+    }
+
+    /// A cube scrambled by the given number of random moves.
+    #[staticmethod]
+    #[pyo3(signature = (moves = 100))]
+    fn random(moves: u8) -> Self {
+        PyFaceletCube(FaceletCube::random(moves))
+    }
+
+    fn to_cubie_cube(&self) -> PyCubieCube {
+        PyCubieCube(CubieCube::from(self.0))
+    }
+
+    fn apply(&self, moves: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Ok(PyFaceletCube(self.0.apply_moves(&extract_moves(moves)?)))
+    }
+
+    fn is_solved(&self) -> bool {
+        self.0 == FaceletCube::default()
+    }
+
+    /// Raises `ValueError` if the cube can not be reached from solved.
+    fn verify(&self) -> PyResult<()> {
+        self.0.verify().map_err(value_error)
+    }
+
+    fn solve(&self) -> PyResult<Vec<PyMove>> {
+        self.0.verify().map_err(value_error)?;
+        solve_cubie_cube(&CubieCube::from(self.0))
+    }
+
+    /// Finds a short solution using only the permitted face turns. Slice moves such as M are
+    /// not supported and raise `ValueError`.
+    fn solve_with(&self, permitted_moves: &Bound<'_, PyAny>) -> PyResult<Vec<PyMove>> {
+        self.0.verify().map_err(value_error)?;
+        solve_cubie_cube_with(&CubieCube::from(self.0), permitted_moves)
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("FaceletCube('{}')", self.0)
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python) -> PyObject {
+        compare(&self.0, &other.0, op, py)
+    }
+
+    fn __hash__(&self) -> u64 {
+        hash(&self.0)
+    }
+
+    fn __getnewargs__(&self) -> (String,) {
+        (self.0.to_string(),)
+    }
+}
+
+/// Parses space separated moves, e.g. "R U R' U'".
+#[pyfunction]
+fn parse_moves(moves: &str) -> PyResult<Vec<PyMove>> {
+    moves
+        .parse::<MoveSequence>()
+        .map(|sequence| to_py_moves(sequence.0))
+        .map_err(value_error)
+}
+
+/// A random WCA-style scramble, which never turns a face twice in a row or again straight after
+/// its opposite face.
+#[pyfunction]
+#[pyo3(signature = (length = 25))]
+fn scramble(length: usize) -> Vec<PyMove> {
+    to_py_moves(random_scramble(length))
+}
+
+#[pymodule]
+fn rubik_cube_solver(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyMove>()?;
+    module.add_class::<PyCubieCube>()?;
+    module.add_class::<PyFaceletCube>()?;
+    module.add_function(wrap_pyfunction!(parse_moves, module)?)?;
+    module.add_function(wrap_pyfunction!(scramble, module)?)?;
+    Ok(())
+}
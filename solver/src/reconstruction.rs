@@ -0,0 +1,443 @@
+//! Reconstructions of timed solves, split into the steps of the method used to solve them.
+//!
+//! As moves only turn faces, the centres never move and each step is found by checking which
+//! pieces are in their home positions after every move; the cross (or first block) is taken to
+//! be on whichever face was completed first, and every later step is checked relative to it.
+
+use std::fmt::Write;
+
+use crate::cube::Cube;
+use crate::cubie_cube::{CubieCube, CORNER_FACES, EDGE_FACES};
+use crate::facelet_cube::FaceletCube;
+use crate::moves::Direction::*;
+use crate::moves::Position::{self, *};
+use crate::moves::{Move, MoveSequence};
+
+/// Faces in the order cross and block faces are preferred when several are completed at once.
+static FACES: [Position; 6] = [Down, Up, Left, Right, Front, Back];
+
+static PAIR_NAMES: [&str; 4] = ["1st pair", "2nd pair", "3rd pair", "4th pair"];
+
+/// Where the face's stickers start in the facelets.
+fn facelet_offset(face: Position) -> usize {
+    9 * [Up, Right, Front, Down, Left, Back]
+        .iter()
+        .position(|&f| f == face)
+        .unwrap()
+}
+
+/// The number of solved corner and edge pairs beside the cross face.
+fn pairs_solved(cube: &CubieCube, cross: Position) -> usize {
+    (0..8)
+        .filter(|&corner| CORNER_FACES[corner].contains(&cross))
+        .filter(|&corner| {
+            let edge = (0..12).find(|&edge| {
+                EDGE_FACES[edge]
+                    .iter()
+                    .all(|&face| face != cross && CORNER_FACES[corner].contains(&face))
+            });
+            cube.corner_solved(corner) && edge.is_some_and(|edge| cube.edge_solved(edge))
+        })
+        .count()
+}
+
+/// Whether the 1x2x3 block on the `side` face, along the `bottom` face, is solved.
+fn block_solved(cube: &CubieCube, side: Position, bottom: Position) -> bool {
+    let top = bottom.opposite();
+    (0..8)
+        .filter(|&corner| CORNER_FACES[corner].contains(&side))
+        .filter(|&corner| !CORNER_FACES[corner].contains(&top))
+        .all(|corner| cube.corner_solved(corner))
+        && (0..12)
+            .filter(|&edge| EDGE_FACES[edge].contains(&side))
+            .filter(|&edge| !EDGE_FACES[edge].contains(&top))
+            .all(|edge| cube.edge_solved(edge))
+}
+
+fn face_oriented(cube: &FaceletCube, face: Position) -> bool {
+    let offset = facelet_offset(face);
+    cube.state[offset..offset + 9]
+        .iter()
+        .all(|&colour| colour == cube.state[offset + 4])
+}
+
+/// Whether the face's corners are solved relative to one another, i.e. up to a turn of the face.
+fn corners_solved_up_to(cube: &CubieCube, face: Position) -> bool {
+    let mut turned = *cube;
+    (0..4).any(|_| {
+        turned = turned.apply_move(&Move(face, Normal));
+        (0..8)
+            .filter(|&corner| CORNER_FACES[corner].contains(&face))
+            .all(|corner| turned.corner_solved(corner))
+    })
+}
+
+/// Whether a step is complete after the given number of moves.
+type Milestone<'a> = Box<dyn Fn(usize) -> bool + 'a>;
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum Method {
+    /// Cross, the four F2L pairs, OLL then PLL.
+    Cfop,
+    /// First block, second block, CMLL then LSE.
+    Roux,
+}
+
+/// A move made `time` milliseconds after the solve started.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct TimedMove {
+    pub action: Move,
+    pub time: u32,
+}
+
+/// A stretch of the solve ending once one (or more, when completed by the same move) of the
+/// method's steps is complete.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub name: String,
+    pub moves: Vec<TimedMove>,
+    /// The milliseconds between the previous step ending and this step's first move (e.g. spent
+    /// recognising the case).
+    pub pause: u32,
+    /// The milliseconds between the previous step ending and this step's last move.
+    pub duration: u32,
+}
+
+fn turns_per_second(moves: usize, duration: u32) -> f32 {
+    if duration == 0 {
+        0.0
+    } else {
+        moves as f32 * 1000.0 / duration as f32
+    }
+}
+
+fn seconds(millis: u32) -> String {
+    format!("{:.2}s", millis as f32 / 1000.0)
+}
+
+impl Step {
+    /// The turns per second across the step, pause included.
+    pub fn tps(&self) -> f32 {
+        turns_per_second(self.moves.len(), self.duration)
+    }
+}
+
+/// A solve, as the scramble and the timed moves made to solve it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reconstruction {
+    pub scramble: Vec<Move>,
+    pub moves: Vec<TimedMove>,
+}
+
+impl Reconstruction {
+    pub fn new(scramble: Vec<Move>, moves: Vec<TimedMove>) -> Result<Self, &'static str> {
+        if moves.windows(2).any(|pair| pair[1].time < pair[0].time) {
+            return Err("Move times must not decrease");
+        }
+        Ok(Reconstruction { scramble, moves })
+    }
+
+    /// The scrambled cube followed by the cube after each move.
+    pub fn replay(&self) -> Vec<FaceletCube> {
+        let mut cubes = vec![FaceletCube::default().apply_moves(&self.scramble)];
+        for timed in self.moves.iter() {
+            cubes.push(cubes[cubes.len() - 1].apply_move(&timed.action));
+        }
+        cubes
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.replay().last() == Some(&FaceletCube::default())
+    }
+
+    /// The milliseconds from the solve starting to its last move.
+    pub fn time(&self) -> u32 {
+        self.moves.last().map_or(0, |timed| timed.time)
+    }
+
+    pub fn tps(&self) -> f32 {
+        turns_per_second(self.moves.len(), self.time())
+    }
+
+    /// Splits the solve into the method's steps. Moves after the last step completed (if the
+    /// solve was not finished) form a final step named after the step they were working towards.
+    pub fn steps(&self, method: Method) -> Vec<Step> {
+        let cubes = self.replay();
+        let cubies: Vec<CubieCube> = cubes.iter().map(|&cube| CubieCube::from(cube)).collect();
+        let first = |done: &dyn Fn(usize) -> bool| (0..cubes.len()).find(|&idx| done(idx));
+
+        let mut milestones: Vec<(&str, Milestone)> = vec![];
+        match method {
+            Method::Cfop => {
+                let cross = FACES
+                    .iter()
+                    .filter_map(|&face| {
+                        first(&|idx| cubies[idx].cross_solved(face)).map(|idx| (idx, face))
+                    })
+                    .min_by_key(|&(idx, _)| idx)
+                    .map_or(Down, |(_, face)| face);
+                let cubies = &cubies;
+                let cross_done = move |idx: usize| cubies[idx].cross_solved(cross);
+
+                milestones.push(("cross", Box::new(cross_done)));
+                for (pairs, &name) in PAIR_NAMES.iter().enumerate() {
+                    milestones.push((
+                        name,
+                        Box::new(move |idx| {
+                            cross_done(idx) && pairs_solved(&cubies[idx], cross) > pairs
+                        }),
+                    ));
+                }
+                let cubes = &cubes;
+                milestones.push((
+                    "OLL",
+                    Box::new(move |idx| {
+                        cross_done(idx)
+                            && pairs_solved(&cubies[idx], cross) == 4
+                            && face_oriented(&cubes[idx], cross.opposite())
+                    }),
+                ));
+            }
+            Method::Roux => {
+                let blocks: Vec<(Position, Position)> = FACES
+                    .iter()
+                    .flat_map(|&bottom| {
+                        FACES
+                            .iter()
+                            .filter(move |&&side| side != bottom && side != bottom.opposite())
+                            .map(move |&side| (side, bottom))
+                    })
+                    .collect();
+                let (side, bottom) = blocks
+                    .iter()
+                    .cloned()
+                    .filter_map(|(side, bottom)| {
+                        first(&|idx| block_solved(&cubies[idx], side, bottom))
+                            .map(|idx| (idx, (side, bottom)))
+                    })
+                    .min_by_key(|&(idx, _)| idx)
+                    .map_or((Left, Down), |(_, block)| block);
+                let cubies = &cubies;
+                let first_block = move |idx: usize| block_solved(&cubies[idx], side, bottom);
+                let second_block = move |idx: usize| {
+                    first_block(idx) && block_solved(&cubies[idx], side.opposite(), bottom)
+                };
+
+                milestones.push(("first block", Box::new(first_block)));
+                milestones.push(("second block", Box::new(second_block)));
+                milestones.push((
+                    "CMLL",
+                    Box::new(move |idx| {
+                        second_block(idx) && corners_solved_up_to(&cubies[idx], bottom.opposite())
+                    }),
+                ));
+            }
+        }
+        let last = match method {
+            Method::Cfop => "PLL",
+            Method::Roux => "LSE",
+        };
+        milestones.push((last, Box::new(|idx| cubes[idx] == FaceletCube::default())));
+
+        // Each step ends at the first move after the previous step's end which completes it.
+        let mut steps: Vec<Step> = vec![];
+        let mut pending: Vec<&str> = vec![];
+        let mut end = 0;
+        for (name, done) in milestones.iter() {
+            let Some(idx) = (end..cubes.len()).find(|&idx| done(idx)) else {
+                if end < self.moves.len() {
+                    pending.push(name);
+                    steps.push(self.step(&pending, end, self.moves.len()));
+                }
+                return steps;
+            };
+            pending.push(name);
+            if idx > end {
+                steps.push(self.step(&pending, end, idx));
+                pending.clear();
+                end = idx;
+            } else if let Some(step) = steps.last_mut() {
+                step.name = format!("{} + {}", step.name, name);
+                pending.clear();
+            }
+        }
+        steps
+    }
+
+    fn step(&self, names: &[&str], start: usize, end: usize) -> Step {
+        let previous = if start == 0 {
+            0
+        } else {
+            self.moves[start - 1].time
+        };
+        Step {
+            name: names.join(" + "),
+            moves: self.moves[start..end].to_vec(),
+            pause: self.moves[start].time - previous,
+            duration: self.moves[end - 1].time - previous,
+        }
+    }
+
+    /// Writes the reconstruction as reconstruction sites (e.g. alg.cubing.net) expect it: the
+    /// scramble, then a line of moves per step with its statistics in a comment.
+    pub fn to_text(&self, method: Method) -> String {
+        let mut text = format!(
+            "{} // scramble\n\n",
+            MoveSequence::from(self.scramble.as_slice())
+        );
+        for step in self.steps(method) {
+            writeln!(
+                text,
+                "{} // {} ({} moves, {}, {:.2} TPS, {} pause)",
+                MoveSequence(step.moves.iter().map(|timed| timed.action).collect()),
+                step.name,
+                step.moves.len(),
+                seconds(step.duration),
+                step.tps(),
+                seconds(step.pause)
+            )
+            .unwrap();
+        }
+        write!(
+            text,
+            "\n// {} moves in {}, {:.2} TPS",
+            self.moves.len(),
+            seconds(self.time()),
+            self.tps()
+        )
+        .unwrap();
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(notation: &str) -> Vec<Move> {
+        notation.parse::<MoveSequence>().unwrap().0
+    }
+
+    /// Reconstructs the solve made of the given steps, scrambling with its inverse and making a
+    /// move every 200ms, with an extra 800ms taken before each step.
+    fn reconstruct(steps: &[&str]) -> Reconstruction {
+        let solution: Vec<Move> = steps.iter().flat_map(|step| moves(step)).collect();
+        let scramble = solution.iter().rev().map(|m| m.inverse()).collect();
+
+        let mut time = 0;
+        let mut timed = vec![];
+        for step in steps {
+            time += 800;
+            for action in moves(step) {
+                time += 200;
+                timed.push(TimedMove { action, time });
+            }
+        }
+        Reconstruction::new(scramble, timed).unwrap()
+    }
+
+    fn summary(steps: &[Step]) -> Vec<(&str, usize)> {
+        steps
+            .iter()
+            .map(|step| (step.name.as_str(), step.moves.len()))
+            .collect()
+    }
+
+    #[test]
+    fn splits_cfop_steps() {
+        let solve = reconstruct(&[
+            "D2 R' F",
+            "L U' L'",
+            "R' U' R",
+            "L' U' L",
+            "R U R'",
+            "R U R' U R U2 R'",
+            "R U R' U' R' F R2 U' R' U' R U R' F'",
+        ]);
+        let steps = solve.steps(Method::Cfop);
+
+        assert!(solve.is_solved());
+        assert_eq!(
+            vec![
+                ("cross", 3),
+                ("1st pair", 3),
+                ("2nd pair", 3),
+                ("3rd pair", 3),
+                ("4th pair", 3),
+                ("OLL", 7),
+                ("PLL", 14),
+            ],
+            summary(&steps)
+        );
+        assert_eq!(1000, steps[1].pause);
+        assert_eq!(1400, steps[1].duration);
+        assert!((steps[5].tps() - 3.18).abs() < 0.01);
+    }
+
+    #[test]
+    fn splits_roux_steps() {
+        let solve = reconstruct(&[
+            "L2 F D'",
+            "R U R'",
+            "R U R' U R U2 R'",
+            "R U' R U R U R U' R' U' R2",
+        ]);
+
+        assert_eq!(
+            vec![
+                ("first block", 3),
+                ("second block", 3),
+                ("CMLL", 7),
+                ("LSE", 11),
+            ],
+            summary(&solve.steps(Method::Roux))
+        );
+    }
+
+    #[test]
+    fn merges_skipped_steps_and_ends_unfinished_solves() {
+        let solve = reconstruct(&["D2 R' F", "R U R' U R U2 R'"]);
+        let mut unfinished = solve.clone();
+        unfinished.moves.pop();
+
+        assert_eq!(
+            vec![
+                ("cross + 1st pair + 2nd pair + 3rd pair + 4th pair", 3),
+                ("OLL + PLL", 7)
+            ],
+            summary(&solve.steps(Method::Cfop))
+        );
+        assert_eq!(
+            vec![
+                ("cross + 1st pair + 2nd pair + 3rd pair + 4th pair", 3),
+                ("OLL", 6)
+            ],
+            summary(&unfinished.steps(Method::Cfop))
+        );
+    }
+
+    #[test]
+    fn writes_text_for_reconstruction_sites() {
+        let solve = reconstruct(&["D2 R' F", "R U R' U R U2 R'"]);
+        let text = solve.to_text(Method::Cfop);
+
+        assert!(text.starts_with("R U2 R' U' R U' R' F' R D2 // scramble\n\n"));
+        assert!(text
+            .contains("\nR U R' U R U2 R' // OLL + PLL (7 moves, 2.20s, 3.18 TPS, 1.00s pause)\n"));
+        assert!(text.ends_with("\n// 10 moves in 3.60s, 2.78 TPS"));
+        assert!(Reconstruction::new(
+            vec![],
+            vec![
+                TimedMove {
+                    action: Move(Up, Normal),
+                    time: 500
+                },
+                TimedMove {
+                    action: Move(Up, Normal),
+                    time: 400
+                },
+            ]
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,401 @@
+//! Translates solutions into the primitives of a cube-solving robot whose grippers can only turn
+//! some of the faces, rotating the whole cube to bring the others within reach.
+
+use std::fmt;
+
+use crate::moves::Direction::{self, *};
+use crate::moves::Move;
+use crate::moves::Position::{self, *};
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// A whole-cube rotation, turning the cube as its R (x), U (y) or F (z) face would be turned.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct Rotation(pub Axis, pub Direction);
+
+impl fmt::Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let axis = match self.0 {
+            Axis::X => "x",
+            Axis::Y => "y",
+            Axis::Z => "z",
+        };
+        let suffix = match self.1 {
+            Normal => "",
+            Prime => "'",
+            Half => "2",
+        };
+        write!(f, "{}{}", axis, suffix)
+    }
+}
+
+/// A single actuation, with faces given in the robot's frame rather than the cube's.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum RobotAction {
+    /// Moves the grip onto the gripper of the given face, ready for it to turn.
+    Grip(Position),
+    /// Turns the gripped face.
+    Turn(Position, Direction),
+    Rotate(Rotation),
+}
+
+impl fmt::Display for RobotAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RobotAction::Grip(face) => write!(f, "grip {}", Move(face, Normal)),
+            RobotAction::Turn(face, direction) => write!(f, "turn {}", Move(face, direction)),
+            RobotAction::Rotate(rotation) => write!(f, "rotate {}", rotation),
+        }
+    }
+}
+
+/// The robot's actions along with the total time they take to actuate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobotPlan {
+    pub actions: Vec<RobotAction>,
+    pub time: u32,
+}
+
+/// Which faces a robot can turn and rotate the cube about, along with how long (e.g. in
+/// milliseconds) each kind of actuation takes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobotModel {
+    /// The faces, in the robot's frame, with a gripper able to turn them.
+    pub grippers: Vec<Position>,
+    /// The axes the robot can rotate the whole cube about.
+    pub rotation_axes: Vec<Axis>,
+    pub quarter_turn_time: u32,
+    pub half_turn_time: u32,
+    pub quarter_rotation_time: u32,
+    pub half_rotation_time: u32,
+    /// The time taken to move the grip onto a different face, which is needed after every
+    /// rotation as the rotating grippers release the cube.
+    pub grip_time: u32,
+}
+
+impl RobotModel {
+    /// Grippers on the R and D faces, each also able to rotate the cube about its own axis.
+    pub fn two_arm() -> Self {
+        RobotModel {
+            grippers: vec![Right, Down],
+            rotation_axes: vec![Axis::X, Axis::Y],
+            ..Self::timings()
+        }
+    }
+
+    /// Grippers on the L, R, F and B faces, with opposite pairs rotating the cube between them.
+    pub fn four_arm() -> Self {
+        RobotModel {
+            grippers: vec![Left, Right, Front, Back],
+            rotation_axes: vec![Axis::X, Axis::Z],
+            ..Self::timings()
+        }
+    }
+
+    fn timings() -> Self {
+        RobotModel {
+            grippers: vec![],
+            rotation_axes: vec![],
+            quarter_turn_time: 100,
+            half_turn_time: 180,
+            quarter_rotation_time: 150,
+            half_rotation_time: 270,
+            grip_time: 120,
+        }
+    }
+
+    fn turn_time(&self, direction: Direction) -> u32 {
+        match direction {
+            Half => self.half_turn_time,
+            _ => self.quarter_turn_time,
+        }
+    }
+
+    fn rotation_time(&self, direction: Direction) -> u32 {
+        match direction {
+            Half => self.half_rotation_time,
+            _ => self.quarter_rotation_time,
+        }
+    }
+
+    /// The quickest actions which apply the moves, starting with the cube in the robot's frame
+    /// and nothing gripped.
+    pub fn plan(&self, moves: &[Move]) -> Result<RobotPlan, &'static str> {
+        let orientations = orientations();
+        let rotations = self.rotation_paths(&orientations);
+
+        // The quickest plan found so far ending in each orientation and grip.
+        let state = |orientation: usize, grip: Option<Position>| {
+            orientation * 7 + grip.map_or(6, |face| face as usize)
+        };
+        let mut plans: Vec<Option<RobotPlan>> = vec![None; orientations.len() * 7];
+        plans[state(0, None)] = Some(RobotPlan {
+            actions: vec![],
+            time: 0,
+        });
+
+        for &Move(face, direction) in moves {
+            let mut next_plans: Vec<Option<RobotPlan>> = vec![None; plans.len()];
+
+            for (from, plan) in plans.iter().enumerate() {
+                let plan = match plan {
+                    Some(plan) => plan,
+                    None => continue,
+                };
+                let (orientation, grip) = (from / 7, POSITIONS.get(from % 7).copied());
+
+                for (to, path) in rotations[orientation].iter().enumerate() {
+                    let (rotation_time, path) = match path {
+                        Some(path) => path,
+                        None => continue,
+                    };
+                    let gripper = POSITIONS
+                        .iter()
+                        .copied()
+                        .find(|&position| orientations[to][position as usize] == face)
+                        .unwrap();
+                    if !self.grippers.contains(&gripper) {
+                        continue;
+                    }
+
+                    let mut next = plan.clone();
+                    next.actions
+                        .extend(path.iter().map(|&rotation| RobotAction::Rotate(rotation)));
+                    next.time += rotation_time;
+                    let grip = if path.is_empty() { grip } else { None };
+                    if grip != Some(gripper) {
+                        next.actions.push(RobotAction::Grip(gripper));
+                        next.time += self.grip_time;
+                    }
+                    next.actions.push(RobotAction::Turn(gripper, direction));
+                    next.time += self.turn_time(direction);
+
+                    let slot = &mut next_plans[state(to, Some(gripper))];
+                    if !matches!(slot, Some(best) if best.time <= next.time) {
+                        *slot = Some(next);
+                    }
+                }
+            }
+
+            if next_plans.iter().all(Option::is_none) {
+                return Err("The robot can not turn every face");
+            }
+            plans = next_plans;
+        }
+
+        Ok(plans
+            .into_iter()
+            .flatten()
+            .min_by_key(|plan| plan.time)
+            .unwrap())
+    }
+
+    /// The quickest rotations between each pair of orientations, if the robot can make them.
+    fn rotation_paths(&self, orientations: &[Orientation]) -> Vec<Vec<Option<RotationPath>>> {
+        let available: Vec<Rotation> = self
+            .rotation_axes
+            .iter()
+            .flat_map(|&axis| {
+                [Normal, Prime, Half]
+                    .iter()
+                    .map(move |&d| Rotation(axis, d))
+            })
+            .collect();
+
+        (0..orientations.len())
+            .map(|source| {
+                let mut paths: Vec<Option<RotationPath>> = vec![None; orientations.len()];
+                let mut settled = vec![false; orientations.len()];
+                paths[source] = Some((0, vec![]));
+
+                while let Some(current) = (0..orientations.len())
+                    .filter(|&idx| !settled[idx] && paths[idx].is_some())
+                    .min_by_key(|&idx| paths[idx].as_ref().unwrap().0)
+                {
+                    settled[current] = true;
+                    let (time, path) = paths[current].clone().unwrap();
+
+                    for &rotation in available.iter() {
+                        let next = rotate(orientations[current], rotation);
+                        let next = orientations.iter().position(|&o| o == next).unwrap();
+                        let next_time = time + self.rotation_time(rotation.1);
+                        if !matches!(paths[next], Some((t, _)) if t <= next_time) {
+                            let mut next_path = path.clone();
+                            next_path.push(rotation);
+                            paths[next] = Some((next_time, next_path));
+                        }
+                    }
+                }
+
+                paths
+            })
+            .collect()
+    }
+}
+
+static POSITIONS: [Position; 6] = [Up, Down, Left, Right, Front, Back];
+
+/// The time taken by, and the rotations making up, the quickest way between two orientations.
+type RotationPath = (u32, Vec<Rotation>);
+
+/// The cube's face found at each position of the robot's frame.
+type Orientation = [Position; 6];
+
+/// Rotates the cube a quarter turn about the axis, moving the face at each position in the cycle
+/// on to the next.
+fn quarter_cycle(axis: Axis) -> [Position; 4] {
+    match axis {
+        Axis::X => [Front, Up, Back, Down],
+        Axis::Y => [Front, Left, Back, Right],
+        Axis::Z => [Up, Right, Down, Left],
+    }
+}
+
+fn rotate(orientation: Orientation, Rotation(axis, direction): Rotation) -> Orientation {
+    let quarter_turns = match direction {
+        Normal => 1,
+        Half => 2,
+        Prime => 3,
+    };
+    let cycle = quarter_cycle(axis);
+    let mut rotated = orientation;
+    for _ in 0..quarter_turns {
+        let previous = rotated;
+        for idx in 0..4 {
+            rotated[cycle[(idx + 1) % 4] as usize] = previous[cycle[idx] as usize];
+        }
+    }
+    rotated
+}
+
+/// Every orientation of the cube, starting with the cube aligned to the robot's frame.
+fn orientations() -> Vec<Orientation> {
+    let mut orientations = vec![POSITIONS];
+    let mut idx = 0;
+    while idx < orientations.len() {
+        for &axis in [Axis::X, Axis::Y].iter() {
+            let next = rotate(orientations[idx], Rotation(axis, Normal));
+            if !orientations.contains(&next) {
+                orientations.push(next);
+            }
+        }
+        idx += 1;
+    }
+    orientations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::MoveSequence;
+
+    fn moves(moves: &str) -> Vec<Move> {
+        moves.parse::<MoveSequence>().unwrap().0
+    }
+
+    /// Replays the plan, returning the moves it applies to the cube along with its time.
+    fn replay(model: &RobotModel, plan: &RobotPlan) -> (Vec<Move>, u32) {
+        let mut orientation = POSITIONS;
+        let mut grip = None;
+        let mut applied = vec![];
+        let mut time = 0;
+
+        for &action in plan.actions.iter() {
+            match action {
+                RobotAction::Grip(face) => {
+                    assert!(model.grippers.contains(&face));
+                    grip = Some(face);
+                    time += model.grip_time;
+                }
+                RobotAction::Turn(face, direction) => {
+                    assert_eq!(Some(face), grip);
+                    applied.push(Move(orientation[face as usize], direction));
+                    time += model.turn_time(direction);
+                }
+                RobotAction::Rotate(rotation) => {
+                    assert!(model.rotation_axes.contains(&rotation.0));
+                    orientation = rotate(orientation, rotation);
+                    grip = None;
+                    time += model.rotation_time(rotation.1);
+                }
+            }
+        }
+
+        (applied, time)
+    }
+
+    #[test]
+    fn there_are_twenty_four_orientations() {
+        assert_eq!(24, orientations().len());
+        assert_eq!(
+            POSITIONS,
+            rotate(
+                rotate(POSITIONS, Rotation(Axis::Z, Prime)),
+                Rotation(Axis::Z, Normal)
+            )
+        );
+    }
+
+    #[test]
+    fn plans_apply_the_solution() {
+        let solution = moves("R U F' D2 L B R2 U' F2 D L' B2");
+
+        for model in [RobotModel::two_arm(), RobotModel::four_arm()].iter() {
+            let plan = model.plan(&solution).unwrap();
+            assert_eq!((solution.clone(), plan.time), replay(model, &plan));
+        }
+    }
+
+    #[test]
+    fn rotates_only_to_reach_faces() {
+        let plan = RobotModel::two_arm().plan(&moves("U")).unwrap();
+
+        assert_eq!(
+            vec![
+                RobotAction::Rotate(Rotation(Axis::X, Half)),
+                RobotAction::Grip(Down),
+                RobotAction::Turn(Down, Normal),
+            ],
+            plan.actions
+        );
+        assert_eq!(270 + 120 + 100, plan.time);
+        assert_eq!(
+            "rotate x2, grip D, turn D",
+            plan.actions
+                .iter()
+                .map(|action| action.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let every_face = RobotModel {
+            grippers: POSITIONS.to_vec(),
+            ..RobotModel::two_arm()
+        };
+        assert!(every_face
+            .plan(&moves("R L R U"))
+            .unwrap()
+            .actions
+            .iter()
+            .all(|action| !matches!(action, RobotAction::Rotate(_))));
+    }
+
+    #[test]
+    fn unreachable_faces_are_rejected() {
+        let model = RobotModel {
+            rotation_axes: vec![],
+            ..RobotModel::four_arm()
+        };
+
+        assert!(model.plan(&moves("R F")).is_ok());
+        assert_eq!(
+            Err("The robot can not turn every face"),
+            model.plan(&moves("R U"))
+        );
+    }
+}
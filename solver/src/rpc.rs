@@ -0,0 +1,318 @@
+//! A JSON-RPC 2.0 interface to the solver over newline delimited stdin and stdout, so a single
+//! long-running process (with its phase tables loaded once) can serve many requests.
+//!
+//! Each line holds one request, e.g. `{"jsonrpc": "2.0", "id": 1, "method": "solve_cube",
+//! "params": {"cube": "..."}}`, and is answered by one line holding the response; requests
+//! without an id are notifications and go unanswered. The methods mirror the wasm functions:
+//!
+//! - `rand_cube`, returning a random cube's facelets
+//! - `solve_cube` with `cube`, returning the solution's moves
+//! - `solve_cube_in_phases` with `cube`, returning the solution annotated with its phases
+//! - `apply_cube_moves` with `cube` and `moves`, returning the resulting cube's facelets
+//! - `validate_cube` with `cube`, returning `{"valid": true}` or `{"valid": false, "error": ...}`
+//! - `analyse_cube` with `cube`, returning whether it is solved, the deepest Thistlethwaite
+//!   subgroup it already belongs to, and the length of each phase of its solution
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+use crate::cube::Cube;
+use crate::cubie_cube::CubieCube;
+use crate::facelet_cube::FaceletCube;
+use crate::moves::Move;
+use crate::pochmann_solver::{get_permitted_moves, solve_in_phases, Phase, Solution};
+use crate::subgroup::Subgroup;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The cube is well-formed but can not be reached from solved.
+const UNSOLVABLE_CUBE: i64 = -32000;
+
+#[derive(Debug)]
+struct RpcError(i64, String);
+
+impl From<&'static str> for RpcError {
+    fn from(err: &'static str) -> Self {
+        RpcError(INVALID_PARAMS, err.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct CubeParams {
+    cube: String,
+}
+
+impl CubeParams {
+    fn verified(&self) -> Result<FaceletCube, RpcError> {
+        let cube = self.cube.parse::<FaceletCube>()?;
+        cube.verify()
+            .map_err(|err| RpcError(UNSOLVABLE_CUBE, err.to_string()))?;
+        Ok(cube)
+    }
+}
+
+#[derive(Deserialize)]
+struct ApplyParams {
+    cube: String,
+    moves: Vec<String>,
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|err| RpcError(INVALID_PARAMS, err.to_string()))
+}
+
+fn call(method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "rand_cube" => Ok(json!(FaceletCube::random(100))),
+        "solve_cube" => Ok(json!(solution(params)?.moves)),
+        "solve_cube_in_phases" => Ok(json!(solution(params)?)),
+        "apply_cube_moves" => {
+            let params: ApplyParams = parse_params(params)?;
+            let moves = params
+                .moves
+                .iter()
+                .map(|action| action.parse())
+                .collect::<Result<Vec<Move>, _>>()?;
+            Ok(json!(params
+                .cube
+                .parse::<FaceletCube>()?
+                .apply_moves(&moves)))
+        }
+        "validate_cube" => match parse_params::<CubeParams>(params)?.verified() {
+            Ok(_) => Ok(json!({ "valid": true })),
+            Err(RpcError(UNSOLVABLE_CUBE, err)) => Ok(json!({ "valid": false, "error": err })),
+            Err(err) => Err(err),
+        },
+        "analyse_cube" => analyse(params),
+        _ => Err(RpcError(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{}'", method),
+        )),
+    }
+}
+
+fn solution(params: Value) -> Result<Solution, RpcError> {
+    let cube = parse_params::<CubeParams>(params)?.verified()?;
+    solve_in_phases(&CubieCube::from(cube))
+        .ok_or_else(|| RpcError(UNSOLVABLE_CUBE, "Cube has no solution".to_string()))
+}
+
+lazy_static! {
+    /// The subgroups each Thistlethwaite phase brings the cube into, smallest first.
+    static ref THISTLETHWAITE_SUBGROUPS: Vec<(&'static str, Subgroup)> = [
+        (Phase::Three, Phase::Four),
+        (Phase::Two, Phase::Three),
+        (Phase::One, Phase::Two),
+    ]
+    .iter()
+    .map(|&(phase, next)| (phase.subgroup(), Subgroup::generated_by(&get_permitted_moves(next))))
+    .collect();
+}
+
+fn analyse(params: Value) -> Result<Value, RpcError> {
+    let cube = parse_params::<CubeParams>(params.clone())?.verified()?;
+    let solution = solution(params)?;
+    let solved = cube == FaceletCube::default();
+    let subgroup = if solved {
+        Some(Phase::Four.subgroup())
+    } else {
+        THISTLETHWAITE_SUBGROUPS
+            .iter()
+            .find(|(_, subgroup)| subgroup.contains(&CubieCube::from(cube)))
+            .map(|&(name, _)| name)
+    };
+
+    Ok(json!({
+        "solved": solved,
+        "subgroup": subgroup,
+        "phaseLengths": solution.phases.iter().map(|phase| phase.moves.len()).collect::<Vec<_>>(),
+        "solutionLength": solution.moves.len(),
+    }))
+}
+
+fn error_response(id: Value, RpcError(code, message): RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Answers a single line of input, returning `None` for notifications.
+fn respond(line: &str) -> Option<Value> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(err) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError(PARSE_ERROR, err.to_string()),
+            ))
+        }
+    };
+    let id = request.get("id").cloned();
+
+    let request = match serde_json::from_value::<Request>(request) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(_) => {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                RpcError(
+                    INVALID_REQUEST,
+                    "Only JSON-RPC 2.0 is supported".to_string(),
+                ),
+            ))
+        }
+        Err(err) => {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                RpcError(INVALID_REQUEST, err.to_string()),
+            ))
+        }
+    };
+
+    let result = call(&request.method, request.params);
+    let id = request.id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(err) => error_response(id, err),
+    })
+}
+
+/// Serves JSON-RPC requests read line by line from `input` until it is exhausted, writing each
+/// response as a line of `output`.
+pub fn serve_rpc<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = respond(&line) {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, params: Value) -> Value {
+        respond(
+            &json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn solves_cubes() {
+        let cube = FaceletCube::random(50);
+        let response = request("solve_cube", json!({ "cube": cube }));
+
+        let moves: Vec<Move> = response["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|action| action.as_str().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(FaceletCube::default(), cube.apply_moves(&moves));
+        assert_eq!(1, response["id"]);
+    }
+
+    #[test]
+    fn applies_moves() {
+        let response = request(
+            "apply_cube_moves",
+            json!({ "cube": FaceletCube::default(), "moves": ["R"] }),
+        );
+
+        assert_eq!(
+            "WWGWWGWWGRRRRRRRRRGGYGGYGGYYYBYYBYYBOOOOOOOOOWBBWBBWBB",
+            response["result"]
+        );
+    }
+
+    #[test]
+    fn validates_and_analyses_cubes() {
+        let twisted = "WWWWWWWWGWRRRRRRRRGGRGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB";
+        let half_turns =
+            FaceletCube::default().apply_moves(&["R2".parse().unwrap(), "U2".parse().unwrap()]);
+
+        assert_eq!(
+            json!({ "valid": false, "error": "Corner twist is invalid" }),
+            request("validate_cube", json!({ "cube": twisted }))["result"]
+        );
+        assert_eq!(
+            UNSOLVABLE_CUBE,
+            request("analyse_cube", json!({ "cube": twisted }))["error"]["code"]
+        );
+
+        let analysis = request("analyse_cube", json!({ "cube": half_turns }))["result"].clone();
+        assert_eq!(false, analysis["solved"]);
+        assert_eq!("<U2, D2, L2, R2, F2, B2>", analysis["subgroup"]);
+        assert_eq!(Some(4), analysis["phaseLengths"].as_array().map(Vec::len));
+        assert_eq!(
+            "<U, D, L, R, F2, B2>",
+            request(
+                "analyse_cube",
+                json!({ "cube": FaceletCube::default().apply_moves(&["L".parse().unwrap()]) })
+            )["result"]["subgroup"]
+        );
+        assert_eq!(
+            Value::Null,
+            request(
+                "analyse_cube",
+                json!({ "cube": FaceletCube::default().apply_moves(&["F".parse().unwrap()]) })
+            )["result"]["subgroup"]
+        );
+    }
+
+    #[test]
+    fn reports_protocol_errors() {
+        assert_eq!(PARSE_ERROR, respond("{").unwrap()["error"]["code"]);
+        assert_eq!(
+            INVALID_REQUEST,
+            respond(r#"{"jsonrpc": "1.0", "id": 1, "method": "rand_cube"}"#).unwrap()["error"]
+                ["code"]
+        );
+        assert_eq!(
+            METHOD_NOT_FOUND,
+            request("solve_everything", Value::Null)["error"]["code"]
+        );
+        assert_eq!(
+            INVALID_PARAMS,
+            request("solve_cube", json!({ "cube": "W" }))["error"]["code"]
+        );
+    }
+
+    #[test]
+    fn notifications_are_not_answered() {
+        let mut output = vec![];
+        serve_rpc(
+            concat!(
+                r#"{"jsonrpc": "2.0", "method": "rand_cube"}"#,
+                "\n\n",
+                r#"{"jsonrpc": "2.0", "id": "a", "method": "rand_cube"}"#,
+                "\n"
+            )
+            .as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(1, output.lines().count());
+        assert!(output.contains(r#""id":"a""#));
+    }
+}
@@ -0,0 +1,287 @@
+//! Random-move scrambles following the WCA's conventions: no face is turned twice in a row, and
+//! a face is not turned again straight after only its opposite face was turned (as in `R L R`,
+//! which is just `R2 L`).
+//!
+//! Scrambles can also be filtered, rejecting those which leave the cube too close to solved or
+//! with too much of it already built.
+
+use rand::prelude::SliceRandom;
+use std::str::FromStr;
+
+use crate::cube::Cube;
+use crate::cubie_cube::CubieCube;
+use crate::facelet_cube::FaceletCube;
+use crate::moves::Position::*;
+use crate::moves::{Move, MoveSequence};
+use crate::pochmann_solver::solve;
+
+pub const DEFAULT_SCRAMBLE_LENGTH: usize = 25;
+
+/// Distances up to this many moves are found exactly by searching every shorter sequence; longer
+/// ones are estimated by the solver, whose solutions are rarely optimal.
+const EXACT_DISTANCE: usize = 5;
+
+/// Filtered scrambles are given up on after this many are rejected.
+const MAX_ATTEMPTS: usize = 1000;
+
+/// Whether turning the face after the given moves would merge with one of them.
+fn is_redundant(before: &[Move], next: &Move) -> bool {
+    match before {
+        [.., last] if last.0 == next.0 => true,
+        [.., second_last, last] => second_last.0 == next.0 && last.0 == next.0.opposite(),
+        _ => false,
+    }
+}
+
+/// A random scramble of the given length.
+pub fn random_scramble(length: usize) -> Vec<Move> {
+    let mut rng = rand::thread_rng();
+    let mut moves: Vec<Move> = vec![];
+    while moves.len() < length {
+        let action = *Move::available().choose(&mut rng).unwrap();
+        if !is_redundant(&moves, &action) {
+            moves.push(action);
+        }
+    }
+    moves
+}
+
+/// Parses a scramble, checking it follows the WCA's conventions.
+pub fn parse_scramble(scramble: &str) -> Result<Vec<Move>, &'static str> {
+    let sequence: MoveSequence = scramble.parse()?;
+    let mut moves: Vec<Move> = vec![];
+    for action in sequence.0 {
+        if let Some(last) = moves.last() {
+            if last.0 == action.0 {
+                return Err("Scramble turns the same face twice in a row");
+            }
+        }
+        if is_redundant(&moves, &action) {
+            return Err("Scramble turns a face again after only turning its opposite face");
+        }
+        moves.push(action);
+    }
+    Ok(moves)
+}
+
+/// The cube the scramble leaves a solved cube in.
+pub fn scrambled_cube(moves: &[Move]) -> FaceletCube {
+    FaceletCube::default().apply_moves(moves)
+}
+
+/// The WCA events scrambled on the 3x3x3 cube, each with its own filtering.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum Event {
+    Speedsolve,
+    OneHanded,
+    Blindfolded,
+    FewestMoves,
+}
+
+impl FromStr for Event {
+    type Err = &'static str;
+
+    /// Parses the WCA's event ids, e.g. "333oh".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "333" => Ok(Event::Speedsolve),
+            "333oh" => Ok(Event::OneHanded),
+            "333bf" => Ok(Event::Blindfolded),
+            "333fm" => Ok(Event::FewestMoves),
+            _ => Err("Unknown event"),
+        }
+    }
+}
+
+/// Which scrambled states are too easy to be used.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct ScrambleFilter {
+    /// States which can be solved in fewer moves are rejected. Distances of up to 5 moves are
+    /// found exactly; beyond that the distance is estimated by the solver, whose solutions are
+    /// rarely optimal, so thresholds above 6 may let nearer states through.
+    pub min_distance: usize,
+    /// Rejects states with a cross already built on any face.
+    pub reject_solved_cross: bool,
+    /// Rejects states with more corners and edges than this already solved.
+    pub max_solved_pieces: usize,
+}
+
+impl ScrambleFilter {
+    pub fn for_event(event: Event) -> Self {
+        match event {
+            Event::Speedsolve | Event::OneHanded => ScrambleFilter {
+                min_distance: 4,
+                reject_solved_cross: true,
+                max_solved_pieces: 4,
+            },
+            // Solved pieces save a blindfolded solver from memorising and executing them.
+            Event::Blindfolded => ScrambleFilter {
+                min_distance: 4,
+                reject_solved_cross: true,
+                max_solved_pieces: 2,
+            },
+            // As deep as can be checked exactly.
+            Event::FewestMoves => ScrambleFilter {
+                min_distance: EXACT_DISTANCE + 1,
+                reject_solved_cross: true,
+                max_solved_pieces: 4,
+            },
+        }
+    }
+
+    pub fn accepts(&self, cube: &CubieCube) -> bool {
+        let solved_pieces = (0..8).filter(|&corner| cube.corner_solved(corner)).count()
+            + (0..12).filter(|&edge| cube.edge_solved(edge)).count();
+        let has_cross = [Up, Down, Left, Right, Front, Back]
+            .iter()
+            .any(|&face| cube.cross_solved(face));
+
+        solved_pieces <= self.max_solved_pieces
+            && !(self.reject_solved_cross && has_cross)
+            && !is_within(cube, self.min_distance.saturating_sub(1))
+    }
+}
+
+/// Whether the cube can be solved in at most `depth` moves.
+fn is_within(cube: &CubieCube, depth: usize) -> bool {
+    if let Some(solution) = solve(cube) {
+        if solution.len() <= depth {
+            return true;
+        }
+    }
+    depth <= EXACT_DISTANCE && search(*cube, depth, &mut vec![])
+}
+
+/// Searches each sequence of up to `depth` moves which can not be written more shortly.
+fn search(cube: CubieCube, depth: usize, moves: &mut Vec<Move>) -> bool {
+    if cube == CubieCube::default() {
+        return true;
+    }
+    if depth == 0 {
+        return false;
+    }
+    Move::available().iter().any(|action| {
+        // Turns of opposite faces commute, so are only searched in one order.
+        let commuted = matches!(moves.last(), Some(last) if last.0 == action.0.opposite()
+            && (last.0 as u8) > (action.0 as u8));
+        if is_redundant(moves, action) || commuted {
+            return false;
+        }
+        moves.push(*action);
+        let found = search(cube.apply_move(action), depth - 1, moves);
+        moves.pop();
+        found
+    })
+}
+
+/// A random scramble of the given length which the filter accepts.
+pub fn filtered_scramble(
+    length: usize,
+    filter: &ScrambleFilter,
+) -> Result<Vec<Move>, &'static str> {
+    (0..MAX_ATTEMPTS)
+        .map(|_| random_scramble(length))
+        .find(|scramble| filter.accepts(&CubieCube::default().apply_moves(scramble)))
+        .ok_or("No scramble passing the filter was found")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_scrambles_follow_the_conventions() {
+        for _ in 0..20 {
+            let scramble = random_scramble(DEFAULT_SCRAMBLE_LENGTH);
+
+            assert_eq!(DEFAULT_SCRAMBLE_LENGTH, scramble.len());
+            assert_eq!(
+                Ok(scramble.clone()),
+                parse_scramble(&MoveSequence(scramble.clone()).to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_redundant_scrambles() {
+        assert!(parse_scramble("R U2 L' D F B2").is_ok());
+        assert_eq!(
+            Err("Scramble turns the same face twice in a row"),
+            parse_scramble("R U U2")
+        );
+        assert_eq!(
+            Err("Scramble turns a face again after only turning its opposite face"),
+            parse_scramble("F R L2 R'")
+        );
+        assert_eq!(Err("Unknown move"), parse_scramble("R Uw"));
+    }
+
+    #[test]
+    fn filters_easy_states() {
+        let cube =
+            |scramble: &str| CubieCube::default().apply_moves(&parse_scramble(scramble).unwrap());
+        let t_perm = cube("R U R' U' R' F R2 U' R' U' R U R' F'");
+        let lenient = ScrambleFilter {
+            min_distance: 4,
+            reject_solved_cross: false,
+            max_solved_pieces: 20,
+        };
+
+        assert!(!lenient.accepts(&cube("R U F'")));
+        assert!(lenient.accepts(&cube("R U F' L")));
+        assert!(lenient.accepts(&t_perm));
+        assert!(!ScrambleFilter {
+            reject_solved_cross: true,
+            ..lenient
+        }
+        .accepts(&t_perm));
+        assert!(!ScrambleFilter {
+            max_solved_pieces: 4,
+            ..lenient
+        }
+        .accepts(&t_perm));
+        assert!(ScrambleFilter::for_event(Event::Speedsolve).accepts(&cube(
+            "R U F' L B2 D R' U2 F L' D2 B R2 U' F2 L D' B' R F U2"
+        )));
+    }
+
+    #[test]
+    fn event_distances_are_checked_exactly() {
+        for event in [
+            Event::Speedsolve,
+            Event::OneHanded,
+            Event::Blindfolded,
+            Event::FewestMoves,
+        ]
+        .iter()
+        {
+            assert!(ScrambleFilter::for_event(*event).min_distance <= EXACT_DISTANCE + 1);
+        }
+        let five_moves = CubieCube::default().apply_moves(&parse_scramble("R U F' L D2").unwrap());
+
+        assert!(!ScrambleFilter::for_event(Event::FewestMoves).accepts(&five_moves));
+    }
+
+    #[test]
+    fn filtered_scrambles_pass_the_filter() {
+        let filter = ScrambleFilter::for_event("333bf".parse().unwrap());
+        let scramble = filtered_scramble(DEFAULT_SCRAMBLE_LENGTH, &filter).unwrap();
+
+        assert!(filter.accepts(&CubieCube::default().apply_moves(&scramble)));
+        assert!(filtered_scramble(2, &filter).is_err());
+    }
+
+    #[test]
+    fn scrambles_the_cube() {
+        let scramble = parse_scramble("R U R' U'").unwrap();
+
+        assert_eq!(
+            "WWOWWGWWGRRWBRRWRRGGYGGWGGGYYRYYYYYYBOOOOOOOOBRRBBBBBB",
+            scrambled_cube(&scramble).to_string()
+        );
+        assert_eq!(
+            FaceletCube::default(),
+            scrambled_cube(&scramble).apply_moves(&parse_scramble("U R U' R'").unwrap())
+        );
+    }
+}
@@ -0,0 +1,472 @@
+//! A JSON over HTTP interface to the solver, for callers which would rather not embed wasm.
+//!
+//! Cubes are given in requests either as `"cube"`, the facelet string used throughout the crate,
+//! or as `"cubie"`, the cubie cube JSON (e.g. `{"cp": [...], "co": [...], "ep": [...],
+//! "eo": [...]}`), and are returned in both forms. The endpoints are:
+//!
+//! - `POST /solve` with a cube, returning `{"solution": ["R", "U'", ...]}`
+//! - `GET` or `POST /scramble`, returning a random `{"cube": ..., "cubie": ...}`
+//! - `POST /apply` with a cube and `"moves": ["R", "U'", ...]`, returning the resulting cube
+//! - `POST /validate` with a cube, returning `{"valid": true}` or `{"valid": false, "error": ...}`
+//! - `POST /render` with a cube and optional `"view"`, `"stickering"`, `"arrows"`, `"colours"`
+//!   and `"size"` (16 to 4096 pixels wide), returning an SVG image
+//!
+//! Failures are returned as `{"error": ...}` with a 4xx or 5xx status.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, Read};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Response, Server};
+
+use crate::cube::Cube;
+use crate::cubie_cube::CubieCube;
+use crate::facelet_cube::FaceletCube;
+use crate::moves::Move;
+use crate::pochmann_solver::solve;
+use crate::svg::{render, SvgOptions};
+
+/// Request bodies beyond this many bytes are rejected.
+const MAX_BODY_LENGTH: usize = 64 * 1024;
+
+/// The widths, in pixels, images may be rendered at.
+const IMAGE_SIZES: RangeInclusive<u32> = 16..=4096;
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub address: String,
+    /// The number of requests handled at once.
+    pub workers: usize,
+    /// The number of solves allowed to run at once; solve requests beyond this are turned away.
+    pub max_solves: usize,
+    /// How long a solve request waits for a solution before giving up.
+    pub solve_timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            address: "127.0.0.1:8080".to_string(),
+            workers: 4,
+            max_solves: 4,
+            solve_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HttpError(u16, String);
+
+impl From<&'static str> for HttpError {
+    fn from(err: &'static str) -> Self {
+        HttpError(400, err.to_string())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Reply {
+    fn json(status: u16, body: Value) -> Self {
+        Reply {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CubeRequest {
+    #[serde(default)]
+    cube: Option<String>,
+    #[serde(default)]
+    cubie: Option<CubieCube>,
+}
+
+impl CubeRequest {
+    /// The requested cube, provided it can be solved; unsolvable cubes are unprocessable (422)
+    /// rather than malformed (400).
+    fn verified(&self) -> Result<CubieCube, HttpError> {
+        let unsolvable = |err: &'static str| HttpError(422, err.to_string());
+
+        match (&self.cube, self.cubie) {
+            (Some(facelets), None) => {
+                let cube = facelets.parse::<FaceletCube>()?;
+                cube.verify().map_err(unsolvable)?;
+                Ok(CubieCube::from(cube))
+            }
+            (None, Some(cube)) => {
+                cube.verify().map_err(unsolvable)?;
+                Ok(cube)
+            }
+            _ => Err("Exactly one of cube or cubie must be given".into()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApplyRequest {
+    #[serde(flatten)]
+    state: CubeRequest,
+    moves: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct ScrambleRequest {
+    #[serde(default)]
+    moves: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct RenderRequest {
+    #[serde(flatten)]
+    state: CubeRequest,
+    #[serde(default)]
+    view: Option<String>,
+    #[serde(default)]
+    stickering: Option<String>,
+    #[serde(default)]
+    arrows: Vec<String>,
+    #[serde(default)]
+    colours: Option<Vec<String>>,
+    #[serde(default)]
+    size: Option<u32>,
+}
+
+fn parse_request<'a, T: Deserialize<'a>>(body: &'a str) -> Result<T, HttpError> {
+    serde_json::from_str(body).map_err(|err| HttpError(400, format!("Invalid request: {}", err)))
+}
+
+fn cube_json(cube: CubieCube) -> Value {
+    json!({ "cube": FaceletCube::from(cube), "cubie": cube })
+}
+
+/// Releases a solve slot once the solve it was taken for finishes, even if the request waiting
+/// on it has already timed out.
+struct SolveSlot(Arc<AtomicUsize>);
+
+impl Drop for SolveSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct Service {
+    max_solves: usize,
+    solve_timeout: Duration,
+    solves: Arc<AtomicUsize>,
+}
+
+impl Service {
+    fn new(config: &ServerConfig) -> Self {
+        Service {
+            max_solves: config.max_solves,
+            solve_timeout: config.solve_timeout,
+            solves: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn handle(&self, method: &str, path: &str, body: &str) -> Reply {
+        let result = match (method, path) {
+            ("POST", "/solve") => self.solve(body),
+            ("GET", "/scramble") | ("POST", "/scramble") => self.scramble(body),
+            ("POST", "/apply") => self.apply(body),
+            ("POST", "/validate") => self.validate(body),
+            ("POST", "/render") => self.render(body),
+            (_, "/solve")
+            | (_, "/scramble")
+            | (_, "/apply")
+            | (_, "/validate")
+            | (_, "/render") => Err(HttpError(405, "Method not allowed".to_string())),
+            _ => Err(HttpError(404, "Not found".to_string())),
+        };
+
+        result.unwrap_or_else(|HttpError(status, error)| {
+            Reply::json(status, json!({ "error": error }))
+        })
+    }
+
+    fn solve(&self, body: &str) -> Result<Reply, HttpError> {
+        let cube = parse_request::<CubeRequest>(body)?.verified()?;
+
+        if self.solves.fetch_add(1, Ordering::SeqCst) >= self.max_solves {
+            self.solves.fetch_sub(1, Ordering::SeqCst);
+            return Err(HttpError(503, "Too many solves in progress".to_string()));
+        }
+        let slot = SolveSlot(Arc::clone(&self.solves));
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _slot = slot;
+            let _ = sender.send(solve(&cube));
+        });
+
+        match receiver.recv_timeout(self.solve_timeout) {
+            Ok(Some(solution)) => Ok(Reply::json(200, json!({ "solution": solution }))),
+            Ok(None) => Err(HttpError(422, "Cube has no solution".to_string())),
+            Err(_) => Err(HttpError(504, "Solve timed out".to_string())),
+        }
+    }
+
+    fn scramble(&self, body: &str) -> Result<Reply, HttpError> {
+        let request = if body.trim().is_empty() {
+            ScrambleRequest::default()
+        } else {
+            parse_request(body)?
+        };
+
+        Ok(Reply::json(
+            200,
+            cube_json(CubieCube::random(request.moves.unwrap_or(100))),
+        ))
+    }
+
+    fn apply(&self, body: &str) -> Result<Reply, HttpError> {
+        let request: ApplyRequest = parse_request(body)?;
+        let moves = request
+            .moves
+            .iter()
+            .map(|action| action.parse())
+            .collect::<Result<Vec<Move>, _>>()?;
+
+        Ok(Reply::json(
+            200,
+            cube_json(request.state.verified()?.apply_moves(&moves)),
+        ))
+    }
+
+    fn validate(&self, body: &str) -> Result<Reply, HttpError> {
+        Ok(Reply::json(
+            200,
+            match parse_request::<CubeRequest>(body)?.verified() {
+                Ok(_) => json!({ "valid": true }),
+                Err(HttpError(422, err)) => json!({ "valid": false, "error": err }),
+                Err(err) => return Err(err),
+            },
+        ))
+    }
+
+    fn render(&self, body: &str) -> Result<Reply, HttpError> {
+        let request: RenderRequest = parse_request(body)?;
+        let mut options = SvgOptions::default();
+
+        if let Some(view) = request.view {
+            options.view = view.parse()?;
+        }
+        if let Some(stickering) = request.stickering {
+            options.stickering = stickering.parse()?;
+        }
+        options.arrows = request
+            .arrows
+            .iter()
+            .map(|arrow| arrow.parse())
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(colours) = request.colours {
+            if colours.len() != 6 {
+                return Err("A colour must be given for each face".into());
+            }
+            options.colours.clone_from_slice(&colours);
+        }
+        if let Some(size) = request.size {
+            if !IMAGE_SIZES.contains(&size) {
+                return Err("Size must be between 16 and 4096".into());
+            }
+            options.size = size;
+        }
+
+        Ok(Reply {
+            status: 200,
+            content_type: "image/svg+xml",
+            body: render(&FaceletCube::from(request.state.verified()?), &options),
+        })
+    }
+}
+
+fn read_body(reader: &mut dyn Read) -> Result<String, HttpError> {
+    let mut body = vec![];
+    reader
+        .take(MAX_BODY_LENGTH as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|err| HttpError(400, err.to_string()))?;
+
+    if body.len() > MAX_BODY_LENGTH {
+        return Err(HttpError(413, "Request body is too large".to_string()));
+    }
+    String::from_utf8(body).map_err(|_| "Request body must be UTF-8".into())
+}
+
+/// Serves the solver over HTTP until the listener fails, handling requests on a fixed pool of
+/// worker threads.
+pub fn serve(config: &ServerConfig) -> io::Result<()> {
+    let server = Arc::new(Server::http(&config.address).map_err(io::Error::other)?);
+    let service = Arc::new(Service::new(config));
+
+    let workers: Vec<_> = (0..config.workers.max(1))
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let service = Arc::clone(&service);
+            thread::spawn(move || {
+                while let Ok(mut request) = server.recv() {
+                    let reply = match read_body(request.as_reader()) {
+                        Ok(body) => {
+                            let path = request.url().split('?').next().unwrap_or("").to_string();
+                            service.handle(request.method().as_str(), &path, &body)
+                        }
+                        Err(HttpError(status, error)) => {
+                            Reply::json(status, json!({ "error": error }))
+                        }
+                    };
+
+                    let content_type =
+                        Header::from_bytes(&b"Content-Type"[..], reply.content_type.as_bytes())
+                            .unwrap();
+                    let _ = request.respond(
+                        Response::from_string(reply.body)
+                            .with_status_code(reply.status)
+                            .with_header(content_type),
+                    );
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> Service {
+        Service::new(&ServerConfig::default())
+    }
+
+    fn body(reply: &Reply) -> Value {
+        serde_json::from_str(&reply.body).unwrap()
+    }
+
+    #[test]
+    fn solves_facelet_and_cubie_cubes() {
+        let cube = CubieCube::random(50);
+
+        for request in [
+            json!({ "cube": FaceletCube::from(cube) }),
+            json!({ "cubie": cube }),
+        ]
+        .iter()
+        {
+            let reply = service().handle("POST", "/solve", &request.to_string());
+            assert_eq!(200, reply.status);
+
+            let moves: Vec<Move> = body(&reply)["solution"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|action| action.as_str().unwrap().parse().unwrap())
+                .collect();
+            assert_eq!(CubieCube::default(), cube.apply_moves(&moves));
+        }
+    }
+
+    #[test]
+    fn applies_moves() {
+        let reply = service().handle(
+            "POST",
+            "/apply",
+            &json!({ "cube": FaceletCube::default(), "moves": ["R"] }).to_string(),
+        );
+
+        assert_eq!(
+            "WWGWWGWWGRRRRRRRRRGGYGGYGGYYYBYYBYYBOOOOOOOOOWBBWBBWBB",
+            body(&reply)["cube"]
+        );
+        assert_eq!(
+            json!(CubieCube::default().apply_move(&"R".parse().unwrap())),
+            body(&reply)["cubie"]
+        );
+    }
+
+    #[test]
+    fn validates_cubes() {
+        let twisted = "WWWWWWWWGWRRRRRRRRGGRGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB";
+
+        assert_eq!(
+            json!({ "valid": true }),
+            body(&service().handle(
+                "POST",
+                "/validate",
+                r#"{"cube": "WWWWWWWWWRRRRRRRRRGGGGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB"}"#
+            ))
+        );
+        assert_eq!(
+            json!({ "valid": false, "error": "Corner twist is invalid" }),
+            body(&service().handle("POST", "/validate", &json!({ "cube": twisted }).to_string()))
+        );
+        assert_eq!(
+            422,
+            service()
+                .handle("POST", "/solve", &json!({ "cube": twisted }).to_string())
+                .status
+        );
+    }
+
+    #[test]
+    fn scrambles_and_renders() {
+        let scramble = body(&service().handle("GET", "/scramble", ""));
+        let reply = service().handle(
+            "POST",
+            "/render",
+            &json!({ "cubie": scramble["cubie"], "view": "isometric" }).to_string(),
+        );
+
+        assert_eq!(200, reply.status);
+        assert_eq!("image/svg+xml", reply.content_type);
+        assert_eq!(27, reply.body.matches("<polygon").count());
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        assert_eq!(404, service().handle("POST", "/unknown", "").status);
+        assert_eq!(405, service().handle("GET", "/solve", "").status);
+        assert_eq!(400, service().handle("POST", "/solve", "not json").status);
+        assert_eq!(400, service().handle("POST", "/solve", "{}").status);
+        assert_eq!(
+            400,
+            service()
+                .handle("POST", "/apply", r#"{"cubie": {"cp": [9]}, "moves": []}"#)
+                .status
+        );
+        for size in [0, 4097, u32::MAX].iter() {
+            let body = json!({ "cube": FaceletCube::default(), "size": size }).to_string();
+            assert_eq!(400, service().handle("POST", "/render", &body).status);
+        }
+    }
+
+    #[test]
+    fn limits_concurrent_solves() {
+        let service = Service::new(&ServerConfig {
+            max_solves: 0,
+            ..ServerConfig::default()
+        });
+
+        let reply = service.handle(
+            "POST",
+            "/solve",
+            &json!({ "cube": FaceletCube::random(50) }).to_string(),
+        );
+        assert_eq!(503, reply.status);
+        assert_eq!(0, service.solves.load(Ordering::SeqCst));
+    }
+}
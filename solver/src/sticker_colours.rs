@@ -0,0 +1,341 @@
+//! Classifies sampled sticker colours (e.g. read from a camera image) into a cube.
+//!
+//! Each sample is converted to CIE L*a*b*, where distances roughly follow perceived differences,
+//! and clustered around the six centre stickers with every face's colour covering nine stickers.
+//! As red and orange (and in poor light white and yellow) are easily confused, an assignment
+//! which does not form a solvable cube is repaired by swapping the stickers whose colours were
+//! the closest calls.
+
+use crate::facelet_cube::{Colour, FaceletCube};
+use Colour::*;
+
+static FACES: [Colour; 6] = [U, R, F, D, L, B];
+
+/// How many of the cheapest sticker swaps are considered when repairing an invalid assignment.
+const REPAIR_CANDIDATES: usize = 16;
+const MAX_ITERATIONS: usize = 10;
+
+/// A sticker's sampled colour.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StickerSample {
+    Rgb(u8, u8, u8),
+    /// Hue in degrees, with saturation and value between 0 and 1.
+    Hsv(f32, f32, f32),
+}
+
+impl StickerSample {
+    fn to_rgb(self) -> [f32; 3] {
+        match self {
+            StickerSample::Rgb(r, g, b) => [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0],
+            StickerSample::Hsv(h, s, v) => {
+                let h = h.rem_euclid(360.0) / 60.0;
+                let c = v * s;
+                let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+                let (r, g, b) = match h as u8 {
+                    0 => (c, x, 0.0),
+                    1 => (x, c, 0.0),
+                    2 => (0.0, c, x),
+                    3 => (0.0, x, c),
+                    4 => (x, 0.0, c),
+                    _ => (c, 0.0, x),
+                };
+                let m = v - c;
+                [r + m, g + m, b + m]
+            }
+        }
+    }
+
+    /// The sample as 8-bit RGB channels.
+    pub(crate) fn to_rgb_bytes(self) -> [u8; 3] {
+        self.to_rgb().map(|channel| (channel * 255.0).round() as u8)
+    }
+
+    fn to_lab(self) -> [f32; 3] {
+        let linear = |channel: f32| {
+            if channel <= 0.04045 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let [r, g, b] = self.to_rgb().map(linear);
+
+        // sRGB to XYZ, relative to the D65 white point.
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.950_47;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.088_83;
+
+        let f = |t: f32| {
+            if t > 0.008_856 {
+                t.cbrt()
+            } else {
+                7.787 * t + 16.0 / 116.0
+            }
+        };
+        [
+            116.0 * f(y) - 16.0,
+            500.0 * (f(x) - f(y)),
+            200.0 * (f(y) - f(z)),
+        ]
+    }
+}
+
+/// The cube read from the samples, along with how sure the classification is of each sticker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColourClassification {
+    pub cube: FaceletCube,
+    /// For each facelet (in URFDLB order), up to 1 when its colour was unmistakable; 0.5 means it
+    /// was a coin toss between two faces, and below that its face was settled by the cube having
+    /// to be solvable rather than by its colour.
+    pub confidence: Vec<f32>,
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f32>()
+        .sqrt()
+}
+
+fn is_centre(facelet: usize) -> bool {
+    facelet % 9 == 4
+}
+
+/// Assigns each sticker a face, nearest first, until each face has nine stickers; centres
+/// always belong to their own face.
+fn assign(distances: &[[f32; 6]]) -> [usize; 54] {
+    let mut faces = [0; 54];
+    let mut counts = [1; 6];
+    let mut assigned = [false; 54];
+    for face in 0..6 {
+        faces[9 * face + 4] = face;
+        assigned[9 * face + 4] = true;
+    }
+
+    let mut candidates: Vec<(usize, usize)> = (0..54)
+        .filter(|&facelet| !is_centre(facelet))
+        .flat_map(|facelet| (0..6).map(move |face| (facelet, face)))
+        .collect();
+    candidates.sort_by(|a, b| distances[a.0][a.1].total_cmp(&distances[b.0][b.1]));
+
+    for (facelet, face) in candidates {
+        if !assigned[facelet] && counts[face] < 9 {
+            faces[facelet] = face;
+            assigned[facelet] = true;
+            counts[face] += 1;
+        }
+    }
+    faces
+}
+
+fn to_cube(faces: &[usize; 54]) -> FaceletCube {
+    let mut cube = FaceletCube::default();
+    for (facelet, &face) in faces.iter().enumerate() {
+        cube.state[facelet] = FACES[face];
+    }
+    cube
+}
+
+/// Finds the cheapest (by the added distance) one or two swaps of stickers between faces which
+/// turn the assignment into a solvable cube.
+fn repair(faces: &[usize; 54], distances: &[[f32; 6]]) -> Option<[usize; 54]> {
+    let mut swaps: Vec<(f32, usize, usize)> = vec![];
+    for a in (0..54).filter(|&facelet| !is_centre(facelet)) {
+        for b in (a + 1..54).filter(|&facelet| !is_centre(facelet)) {
+            let (fa, fb) = (faces[a], faces[b]);
+            if fa != fb {
+                let cost =
+                    distances[a][fb] - distances[a][fa] + distances[b][fa] - distances[b][fb];
+                swaps.push((cost, a, b));
+            }
+        }
+    }
+    swaps.sort_by(|a, b| a.0.total_cmp(&b.0));
+    swaps.truncate(REPAIR_CANDIDATES);
+
+    let apply = |chosen: &[&(f32, usize, usize)]| {
+        let mut swapped = *faces;
+        for &&(_, a, b) in chosen {
+            swapped.swap(a, b);
+        }
+        swapped
+    };
+
+    let mut repairs: Vec<(f32, [usize; 54])> = vec![];
+    for (idx, first) in swaps.iter().enumerate() {
+        repairs.push((first.0, apply(&[first])));
+        for second in swaps[idx + 1..].iter() {
+            let touched = [first.1, first.2];
+            if !touched.contains(&second.1) && !touched.contains(&second.2) {
+                repairs.push((first.0 + second.0, apply(&[first, second])));
+            }
+        }
+    }
+    repairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+    repairs
+        .into_iter()
+        .map(|(_, faces)| faces)
+        .find(|faces| to_cube(faces).verify().is_ok())
+}
+
+/// Classifies samples of the 54 stickers, given in URFDLB facelet order (as in Kociemba's
+/// notation), into the faces whose centre stickers they match.
+pub fn classify_stickers(samples: &[StickerSample]) -> Result<ColourClassification, &'static str> {
+    if samples.len() != 54 {
+        return Err("A sample must be given for each of the 54 stickers");
+    }
+    let colours: Vec<[f32; 3]> = samples.iter().map(|sample| sample.to_lab()).collect();
+
+    // Anchor each face's colour on its centre, then refine it to the mean of its stickers.
+    let mut centroids: Vec<[f32; 3]> = (0..6).map(|face| colours[9 * face + 4]).collect();
+    let mut faces = [0; 54];
+    let mut distances = vec![];
+    for _ in 0..MAX_ITERATIONS {
+        distances = colours
+            .iter()
+            .map(|&colour| {
+                let mut row = [0.0; 6];
+                for (face, &centroid) in centroids.iter().enumerate() {
+                    row[face] = distance(colour, centroid);
+                }
+                row
+            })
+            .collect();
+
+        let next = assign(&distances);
+        if next == faces {
+            break;
+        }
+        faces = next;
+        centroids = (0..6)
+            .map(|face| {
+                let mut mean = [0.0; 3];
+                for (colour, _) in colours.iter().zip(faces.iter()).filter(|(_, &f)| f == face) {
+                    for channel in 0..3 {
+                        mean[channel] += colour[channel] / 9.0;
+                    }
+                }
+                mean
+            })
+            .collect();
+    }
+
+    if to_cube(&faces).verify().is_err() {
+        faces = repair(&faces, &distances).ok_or("Sticker colours do not form a solvable cube")?;
+    }
+
+    let confidence = faces
+        .iter()
+        .enumerate()
+        .map(|(facelet, &face)| {
+            if is_centre(facelet) {
+                return 1.0;
+            }
+            let own = distances[facelet][face];
+            let other = (0..6)
+                .filter(|&f| f != face)
+                .map(|f| distances[facelet][f])
+                .fold(f32::INFINITY, f32::min);
+            if own + other == 0.0 {
+                0.5
+            } else {
+                other / (own + other)
+            }
+        })
+        .collect();
+
+    Ok(ColourClassification {
+        cube: to_cube(&faces),
+        confidence,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::moves::MoveSequence;
+
+    static RGB: [(u8, u8, u8); 6] = [
+        (235, 235, 230),
+        (190, 25, 35),
+        (20, 155, 75),
+        (235, 220, 45),
+        (245, 115, 20),
+        (25, 75, 200),
+    ];
+
+    fn scrambled() -> FaceletCube {
+        let moves = "R U F' L2 D B' R2 U' F".parse::<MoveSequence>().unwrap();
+        FaceletCube::default().apply_moves(&moves.0)
+    }
+
+    /// Samples of the cube's stickers under slightly uneven lighting.
+    fn samples(cube: &FaceletCube) -> Vec<StickerSample> {
+        cube.state
+            .iter()
+            .enumerate()
+            .map(|(facelet, &colour)| {
+                let (r, g, b) = RGB[colour as usize];
+                let shade = |channel: u8| {
+                    (channel as i32 + (facelet as i32 * 7) % 21 - 10).clamp(0, 255) as u8
+                };
+                StickerSample::Rgb(shade(r), shade(g), shade(b))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn classifies_distinct_colours() {
+        let cube = scrambled();
+        let classification = classify_stickers(&samples(&cube)).unwrap();
+
+        assert_eq!(cube, classification.cube);
+        assert!(classification.confidence.iter().all(|&c| c > 0.6));
+    }
+
+    #[test]
+    fn repairs_confused_red_and_orange() {
+        let cube = scrambled();
+        let mut samples = samples(&cube);
+        let red = (0..54)
+            .find(|&f| !is_centre(f) && cube.state[f] == Colour::R)
+            .unwrap();
+        let orange = (0..54)
+            .find(|&f| !is_centre(f) && cube.state[f] == Colour::L)
+            .unwrap();
+        samples[red] = StickerSample::Rgb(235, 95, 30);
+        samples[orange] = StickerSample::Rgb(200, 45, 35);
+
+        let classification = classify_stickers(&samples).unwrap();
+
+        assert_eq!(cube, classification.cube);
+        assert!(classification.confidence[red] < 0.5);
+        assert!(classification.confidence[orange] < 0.5);
+    }
+
+    #[test]
+    fn accepts_hsv_samples() {
+        let hsv = [
+            (0.0, 0.02, 0.93),
+            (355.0, 0.87, 0.75),
+            (145.0, 0.87, 0.61),
+            (55.0, 0.81, 0.92),
+            (25.0, 0.92, 0.96),
+            (223.0, 0.88, 0.78),
+        ];
+        let cube = scrambled();
+        let samples: Vec<StickerSample> = cube
+            .state
+            .iter()
+            .map(|&colour| {
+                let (h, s, v) = hsv[colour as usize];
+                StickerSample::Hsv(h, s, v)
+            })
+            .collect();
+
+        assert_eq!(cube, classify_stickers(&samples).unwrap().cube);
+        assert!(classify_stickers(&samples[..9]).is_err());
+    }
+}
@@ -0,0 +1,1000 @@
+use lazy_static::lazy_static;
+use rand::prelude::SliceRandom;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::cubie_cube::CubieCube;
+use crate::facelet_cube::{
+    facelet_location, FaceletCube, Vector, CORNER_COLOURS, CORNER_FACELETS, EDGE_COLOURS,
+    EDGE_FACELETS,
+};
+use crate::moves::Direction::*;
+use crate::moves::Position::*;
+use crate::moves::{Move, Slice, Turn};
+
+const STICKERS: usize = 54;
+
+/// The facelets at the centre of each face.
+const CENTRES: [usize; 6] = [4, 13, 22, 31, 40, 49];
+
+/// Distance tables are kept to at most this many entries, following fewer pieces when all of
+/// them would need more.
+const TABLE_LIMIT: usize = 1 << 22;
+
+/// Shortest solutions are given up on after expanding this many states, and a solution at most
+/// twice as long is searched for instead.
+const OPTIMAL_SEARCH_BUDGET: usize = 20_000_000;
+
+type Permutation = [u8; STICKERS];
+
+const IDENTITY: Permutation = {
+    let mut perm = [0; STICKERS];
+    let mut idx = 0;
+    while idx < STICKERS {
+        perm[idx] = idx as u8;
+        idx += 1;
+    }
+    perm
+};
+
+/// Where each sticker of the solved cube has been moved to.
+fn to_permutation(cube: CubieCube) -> Permutation {
+    let mut perm = IDENTITY;
+
+    for (position, (&corner, &co)) in cube.cp.iter().zip(cube.co.iter()).enumerate() {
+        for k in 0..3 {
+            perm[CORNER_FACELETS[corner as usize][k] as usize] =
+                CORNER_FACELETS[position][(k + co as usize) % 3] as u8;
+        }
+    }
+
+    for (position, (&edge, &eo)) in cube.ep.iter().zip(cube.eo.iter()).enumerate() {
+        for k in 0..2 {
+            perm[EDGE_FACELETS[edge as usize][k] as usize] =
+                EDGE_FACELETS[position][(k + eo as usize) % 2] as u8;
+        }
+    }
+
+    perm
+}
+
+/// Where each sticker of the solved cube has been moved to, recognising each piece by its
+/// colours; the centres may be anywhere the whole cube can be turned to.
+fn facelets_to_permutation(faces: &FaceletCube) -> Result<Permutation, &'static str> {
+    let mut perm = IDENTITY;
+    let mut placed = [false; STICKERS];
+
+    for (face, &centre) in CENTRES.iter().enumerate() {
+        let home = CENTRES[faces.state[centre] as usize];
+        perm[home] = CENTRES[face] as u8;
+        placed[home] = true;
+    }
+
+    for position in CORNER_FACELETS.iter() {
+        let colours: Vec<_> = position.iter().map(|&f| faces.state[f as usize]).collect();
+        let (corner, twist) = (0..8)
+            .flat_map(|corner| (0..3).map(move |twist| (corner, twist)))
+            .find(|&(corner, twist)| {
+                (0..3).all(|k| CORNER_COLOURS[corner][k] == colours[(k + twist) % 3])
+            })
+            .ok_or("Facelets do not form valid pieces")?;
+        for k in 0..3 {
+            let home = CORNER_FACELETS[corner][k] as usize;
+            perm[home] = position[(k + twist) % 3] as u8;
+            placed[home] = true;
+        }
+    }
+
+    for position in EDGE_FACELETS.iter() {
+        let colours: Vec<_> = position.iter().map(|&f| faces.state[f as usize]).collect();
+        let (edge, flip) = (0..12)
+            .flat_map(|edge| (0..2).map(move |flip| (edge, flip)))
+            .find(|&(edge, flip)| (0..2).all(|k| EDGE_COLOURS[edge][k] == colours[(k + flip) % 2]))
+            .ok_or("Facelets do not form valid pieces")?;
+        for k in 0..2 {
+            let home = EDGE_FACELETS[edge][k] as usize;
+            perm[home] = position[(k + flip) % 2] as u8;
+            placed[home] = true;
+        }
+    }
+
+    if placed.iter().any(|&placed| !placed) {
+        return Err("Facelets do not form valid pieces");
+    }
+    if held_upright(&perm).is_none() {
+        return Err("Centres are not arranged as on a real cube");
+    }
+    Ok(perm)
+}
+
+/// The permutation once the cube is turned in the hand to bring its centres back into place.
+fn held_upright(perm: &Permutation) -> Option<Permutation> {
+    ROTATIONS
+        .iter()
+        .map(|rotation| compose(perm, rotation))
+        .find(|perm| {
+            CENTRES
+                .iter()
+                .all(|&centre| perm[centre] as usize == centre)
+        })
+}
+
+/// The cube whose stickers are moved as the permutation describes, once it is turned in the
+/// hand to bring its centres back into place.
+fn from_permutation(perm: &Permutation) -> CubieCube {
+    let perm = held_upright(perm).expect("Some rotation returns the centres to their places");
+    let solved = CubieCube::default();
+    let mut cube = solved;
+
+    for corner in 0..8 {
+        let sticker = perm[CORNER_FACELETS[corner][0] as usize];
+        for (position, facelets) in CORNER_FACELETS.iter().enumerate() {
+            if let Some(co) = facelets.iter().position(|&f| f as u8 == sticker) {
+                cube.cp[position] = solved.cp[corner];
+                cube.co[position] = co as u8;
+            }
+        }
+    }
+
+    for edge in 0..12 {
+        let sticker = perm[EDGE_FACELETS[edge][0] as usize];
+        for (position, facelets) in EDGE_FACELETS.iter().enumerate() {
+            if let Some(eo) = facelets.iter().position(|&f| f as u8 == sticker) {
+                cube.ep[position] = solved.ep[edge];
+                cube.eo[position] = eo as u8;
+            }
+        }
+    }
+
+    cube
+}
+
+/// Applies `a` and then `b`.
+fn compose(a: &Permutation, b: &Permutation) -> Permutation {
+    let mut perm = IDENTITY;
+    for (idx, &point) in a.iter().enumerate() {
+        perm[idx] = b[point as usize];
+    }
+    perm
+}
+
+fn invert(a: &Permutation) -> Permutation {
+    let mut perm = IDENTITY;
+    for (idx, &point) in a.iter().enumerate() {
+        perm[point as usize] = idx as u8;
+    }
+    perm
+}
+
+/// A clockwise quarter turn, seen from the end `axis` points to, of the layer `depth` along it.
+fn quarter_turn(axis: Vector, depth: i8) -> Permutation {
+    let dot = |v: Vector| axis[0] * v[0] + axis[1] * v[1] + axis[2] * v[2];
+    let rotate = |v: Vector| {
+        let along = dot(v);
+        [
+            axis[0] * along - (axis[1] * v[2] - axis[2] * v[1]),
+            axis[1] * along - (axis[2] * v[0] - axis[0] * v[2]),
+            axis[2] * along - (axis[0] * v[1] - axis[1] * v[0]),
+        ]
+    };
+
+    let locations: Vec<(Vector, Vector)> = (0..STICKERS).map(facelet_location).collect();
+    let mut perm = IDENTITY;
+    for (idx, &(cubie, normal)) in locations.iter().enumerate() {
+        if dot(cubie) == depth {
+            let moved = (rotate(cubie), rotate(normal));
+            perm[idx] = locations
+                .iter()
+                .position(|&location| location == moved)
+                .expect("Turns move facelets onto facelets") as u8;
+        }
+    }
+    perm
+}
+
+/// The axis a turn is clockwise about, and how far along it the turned layer lies.
+fn layer(turn: Turn) -> (Vector, i8) {
+    match turn {
+        Turn::Face(Move(Up, _)) => ([0, 1, 0], 1),
+        Turn::Face(Move(Down, _)) => ([0, -1, 0], 1),
+        Turn::Face(Move(Left, _)) => ([-1, 0, 0], 1),
+        Turn::Face(Move(Right, _)) => ([1, 0, 0], 1),
+        Turn::Face(Move(Front, _)) => ([0, 0, 1], 1),
+        Turn::Face(Move(Back, _)) => ([0, 0, -1], 1),
+        Turn::Slice(Slice::Middle, _) => ([-1, 0, 0], 0),
+        Turn::Slice(Slice::Equator, _) => ([0, -1, 0], 0),
+        Turn::Slice(Slice::Standing, _) => ([0, 0, 1], 0),
+    }
+}
+
+/// The coordinate axis a turn is about and where its layer lies along that axis, which are equal
+/// exactly when two turns turn the same layer.
+fn layer_position(turn: Turn) -> (usize, i8) {
+    let (axis, depth) = layer(turn);
+    let idx = axis.iter().position(|&component| component != 0).unwrap();
+    (idx, axis[idx] * depth)
+}
+
+fn to_turn_permutation(turn: Turn) -> Permutation {
+    let (axis, depth) = layer(turn);
+    let quarter = quarter_turn(axis, depth);
+    let direction = match turn {
+        Turn::Face(Move(_, direction)) | Turn::Slice(_, direction) => direction,
+    };
+    let quarters = match direction {
+        Normal => 1,
+        Half => 2,
+        Prime => 3,
+    };
+    (0..quarters).fold(IDENTITY, |perm, _| compose(&perm, &quarter))
+}
+
+lazy_static! {
+    /// The 24 ways of turning the whole cube in the hand.
+    static ref ROTATIONS: Vec<Permutation> = {
+        let generators: Vec<Permutation> = [[1, 0, 0], [0, 1, 0], [0, 0, 1]]
+            .iter()
+            .map(|&axis| {
+                (-1..=1).fold(IDENTITY, |perm, depth| compose(&perm, &quarter_turn(axis, depth)))
+            })
+            .collect();
+        let mut rotations = vec![IDENTITY];
+        let mut idx = 0;
+        while idx < rotations.len() {
+            for generator in generators.iter() {
+                let rotation = compose(&rotations[idx], generator);
+                if !rotations.contains(&rotation) {
+                    rotations.push(rotation);
+                }
+            }
+            idx += 1;
+        }
+        rotations
+    };
+
+    /// The distance tables last built.
+    static ref LAST_TABLES: Mutex<Option<BuiltTables>> = Mutex::new(None);
+}
+
+/// Distance tables with the turns and goals they were built for.
+struct BuiltTables {
+    turns: Vec<Turn>,
+    goals: Vec<Permutation>,
+    tables: Arc<Vec<DistanceTable>>,
+}
+
+#[derive(Clone)]
+struct Level {
+    generators: Vec<Permutation>,
+    transversal: Vec<Option<(Permutation, Permutation)>>,
+}
+
+/// The subgroup of cube states reachable using a restricted set of turns.
+///
+/// States are acted on as permutations of all 54 stickers, numbered as the facelets they sit on
+/// when the cube is held with its centres in place. Face turns leave the centres where they
+/// are, whereas slice turns (M, E and S) carry four of them along, so a cube counts as solved
+/// once its stickers are anywhere the whole cube could be turned to in the hand. Membership is
+/// decided by a stabiliser chain built with Knuth's variant of the Schreier-Sims algorithm,
+/// where level `k` holds a transversal of the elements fixing every sticker before `k`.
+///
+/// Solutions are found by iterative deepening A*, pruned by tables of how far each state of
+/// some of the pieces is from solved using the subgroup's turns. When a shortest solution takes
+/// too long to find, the search is repeated with the estimates doubled, which always finds one
+/// at most twice as long.
+pub(crate) struct Subgroup {
+    turns: Vec<Turn>,
+    generators: Vec<Permutation>,
+    /// Whether each turn is redundant straight after each other turn, see `is_redundant`.
+    redundant: Vec<Vec<bool>>,
+    levels: Vec<Level>,
+}
+
+impl Subgroup {
+    pub(crate) fn generated_by(moves: &[Move]) -> Self {
+        let turns: Vec<Turn> = moves.iter().map(|&action| Turn::from(action)).collect();
+        Subgroup::generated_by_turns(&turns)
+    }
+
+    pub(crate) fn generated_by_turns(turns: &[Turn]) -> Self {
+        let mut unique_turns: Vec<Turn> = vec![];
+        for turn in turns {
+            if !unique_turns.contains(turn) {
+                unique_turns.push(*turn);
+            }
+        }
+
+        let mut group = Subgroup {
+            generators: unique_turns
+                .iter()
+                .map(|&turn| to_turn_permutation(turn))
+                .collect(),
+            redundant: unique_turns
+                .iter()
+                .map(|&last| {
+                    unique_turns
+                        .iter()
+                        .map(|&next| is_redundant(&unique_turns, last, next))
+                        .collect()
+                })
+                .collect(),
+            turns: unique_turns,
+            levels: (0..STICKERS)
+                .map(|k| {
+                    let mut transversal = vec![None; STICKERS];
+                    transversal[k] = Some((IDENTITY, IDENTITY));
+                    Level {
+                        generators: vec![],
+                        transversal,
+                    }
+                })
+                .collect(),
+        };
+
+        for generator in group.generators.clone().iter() {
+            group.add_generator(0, *generator);
+        }
+
+        group
+    }
+
+    #[cfg(any(feature = "rpc", test))]
+    pub(crate) fn contains(&self, cube: &CubieCube) -> bool {
+        !self.goals(&to_permutation(*cube)).is_empty()
+    }
+
+    /// Finds a shortest sequence of the subgroup's turns which solves the cube, or one at most
+    /// twice as long when the shortest takes too long to find.
+    pub(crate) fn solve(&self, cube: &CubieCube) -> Result<Vec<Turn>, &'static str> {
+        self.solve_permutation(&to_permutation(*cube))
+    }
+
+    fn solve_permutation(&self, start: &Permutation) -> Result<Vec<Turn>, &'static str> {
+        let goals = self.goals(start);
+        if goals.is_empty() {
+            return Err("Cube is unreachable using the permitted moves");
+        }
+
+        let tables = self.distance_tables(&goals);
+        let solution = Search::new(self, &tables, &goals, 1, OPTIMAL_SEARCH_BUDGET)
+            .run(start)
+            .or_else(|| Search::new(self, &tables, &goals, 2, usize::MAX).run(start))
+            .expect("Searches without a budget always find a solution");
+        Ok(solution)
+    }
+
+    /// A uniformly random member; as each member is the product of exactly one representative
+    /// from every level's transversal, picking each uniformly picks the product uniformly.
+    pub(crate) fn random(&self) -> CubieCube {
+        let mut rng = rand::thread_rng();
+        let perm = self.levels.iter().rev().fold(IDENTITY, |perm, level| {
+            let representatives: Vec<&Permutation> = level
+                .transversal
+                .iter()
+                .flatten()
+                .map(|(representative, _)| representative)
+                .collect();
+            compose(&perm, representatives.choose(&mut rng).unwrap())
+        });
+        from_permutation(&perm)
+    }
+
+    /// The ways of holding a solved cube which the subgroup's turns can reach from the state.
+    fn goals(&self, perm: &Permutation) -> Vec<Permutation> {
+        let inverse = invert(perm);
+        ROTATIONS
+            .iter()
+            .filter(|rotation| self.contains_from(0, compose(&inverse, rotation)))
+            .copied()
+            .collect()
+    }
+
+    /// The distance tables towards the goals, reusing the last ones built when they were for the
+    /// same turns and goals, as building them takes far longer than most searches.
+    fn distance_tables(&self, goals: &[Permutation]) -> Arc<Vec<DistanceTable>> {
+        if let Some(built) = LAST_TABLES.lock().unwrap().as_ref() {
+            if built.turns == self.turns && built.goals == goals {
+                return Arc::clone(&built.tables);
+            }
+        }
+        let tables = Arc::new(self.build_distance_tables(goals));
+        *LAST_TABLES.lock().unwrap() = Some(BuiltTables {
+            turns: self.turns.clone(),
+            goals: goals.to_vec(),
+            tables: Arc::clone(&tables),
+        });
+        tables
+    }
+
+    /// Tables for the corners, edges and centres the subgroup moves, each split between as few
+    /// tables as keep within the size limit.
+    fn build_distance_tables(&self, goals: &[Permutation]) -> Vec<DistanceTable> {
+        let corners: Vec<Vec<u8>> = CORNER_FACELETS
+            .iter()
+            .map(|facelets| facelets.iter().map(|&f| f as u8).collect())
+            .collect();
+        let edges: Vec<Vec<u8>> = EDGE_FACELETS
+            .iter()
+            .map(|facelets| facelets.iter().map(|&f| f as u8).collect())
+            .collect();
+        let centres: Vec<Vec<u8>> = CENTRES.iter().map(|&f| vec![f as u8]).collect();
+
+        let mut tables = vec![];
+        for pieces in [corners, edges, centres].iter() {
+            let slots: Vec<Vec<u8>> = pieces
+                .iter()
+                .filter(|facelets| {
+                    self.generators.iter().any(|generator| {
+                        facelets
+                            .iter()
+                            .any(|&facelet| generator[facelet as usize] != facelet)
+                    })
+                })
+                .cloned()
+                .collect();
+            if slots.is_empty() {
+                continue;
+            }
+
+            let twists = slots[0].len();
+            let mut followed = slots.len();
+            while table_size(slots.len(), followed, twists) > TABLE_LIMIT {
+                followed -= 1;
+            }
+            let mut start = 0;
+            loop {
+                let first = start.min(slots.len() - followed);
+                let stickers = slots[first..first + followed]
+                    .iter()
+                    .map(|facelets| facelets[0])
+                    .collect();
+                tables.push(DistanceTable::new(self, &slots, stickers, goals));
+                start += followed;
+                if start >= slots.len() {
+                    break;
+                }
+            }
+        }
+        tables
+    }
+
+    fn contains_from(&self, level: usize, perm: Permutation) -> bool {
+        let mut perm = perm;
+        for (k, level) in self.levels.iter().enumerate().skip(level) {
+            match level.transversal[perm[k] as usize] {
+                Some((_, ref inverse)) => perm = compose(&perm, inverse),
+                None => return false,
+            }
+        }
+        true
+    }
+
+    fn add_generator(&mut self, level: usize, perm: Permutation) {
+        if self.contains_from(level, perm) {
+            return;
+        }
+
+        self.levels[level].generators.push(perm);
+
+        let representatives: Vec<Permutation> = self.levels[level]
+            .transversal
+            .iter()
+            .flatten()
+            .map(|&(representative, _)| representative)
+            .collect();
+        for representative in representatives.iter() {
+            self.extend_orbit(level, compose(representative, &perm));
+        }
+    }
+
+    fn extend_orbit(&mut self, level: usize, perm: Permutation) {
+        match self.levels[level].transversal[perm[level] as usize] {
+            Some((_, ref inverse)) => {
+                let residue = compose(&perm, inverse);
+                self.add_generator(level + 1, residue);
+            }
+            None => {
+                self.levels[level].transversal[perm[level] as usize] = Some((perm, invert(&perm)));
+                for generator in self.levels[level].generators.clone().iter() {
+                    self.extend_orbit(level, compose(&perm, generator));
+                }
+            }
+        }
+    }
+}
+
+/// An iterative deepening A* search through the subgroup's turns. Its estimates of the turns
+/// left are multiplied by `weight`: beyond 1 they may overestimate, so solutions may be up to
+/// `weight` times longer than the shortest, but far fewer states need to be searched.
+struct Search<'a> {
+    group: &'a Subgroup,
+    tables: &'a [DistanceTable],
+    goals: &'a [Permutation],
+    weight: usize,
+    /// The search gives up once it has expanded this many states.
+    budget: usize,
+    expanded: usize,
+    path: Vec<usize>,
+}
+
+impl<'a> Search<'a> {
+    fn new(
+        group: &'a Subgroup,
+        tables: &'a [DistanceTable],
+        goals: &'a [Permutation],
+        weight: usize,
+        budget: usize,
+    ) -> Self {
+        Search {
+            group,
+            tables,
+            goals,
+            weight,
+            budget,
+            expanded: 0,
+            path: vec![],
+        }
+    }
+
+    /// Searches ever deeper until a path solves the cube, or gives up once the budget is spent.
+    fn run(mut self, start: &Permutation) -> Option<Vec<Turn>> {
+        let mut bound = 0;
+        loop {
+            match self.search(start, bound) {
+                Ok(()) => {
+                    return Some(self.path.iter().map(|&idx| self.group.turns[idx]).collect())
+                }
+                Err(_) if self.expanded >= self.budget => return None,
+                Err(next_bound) => bound = next_bound,
+            }
+        }
+    }
+
+    /// Searches for solutions continuing the path within the bound, returning the smallest
+    /// bound worth trying next when there is none.
+    fn search(&mut self, perm: &Permutation, bound: usize) -> Result<(), usize> {
+        self.expanded += 1;
+        let mut estimate = 0;
+        for table in self.tables.iter() {
+            estimate = estimate.max(table.distance(perm));
+            if self.path.len() + self.weight * estimate > bound {
+                break;
+            }
+        }
+        let cost = self.path.len() + self.weight * estimate;
+        if cost > bound || self.expanded >= self.budget {
+            return Err(cost);
+        }
+        if estimate == 0 && self.goals.contains(perm) {
+            return Ok(());
+        }
+
+        let mut next_bound = usize::MAX;
+        for (idx, generator) in self.group.generators.iter().enumerate() {
+            if let Some(&last) = self.path.last() {
+                if self.group.redundant[last][idx] {
+                    continue;
+                }
+            }
+            self.path.push(idx);
+            match self.search(&compose(perm, generator), bound) {
+                Ok(()) => return Ok(()),
+                Err(exceeded) => next_bound = next_bound.min(exceeded),
+            }
+            self.path.pop();
+        }
+        Err(next_bound)
+    }
+}
+
+/// Whether a search need not turn the layer of `next` straight after `last`: turning the
+/// same layer again merges into a single turn when every turn of that layer is permitted,
+/// and turns of parallel layers commute, so are only searched in one order.
+fn is_redundant(turns: &[Turn], last: Turn, next: Turn) -> bool {
+    let (last_axis, last_position) = layer_position(last);
+    let (next_axis, next_position) = layer_position(next);
+    if last_axis != next_axis || next_position > last_position {
+        return false;
+    }
+    next_position < last_position
+        || turns
+            .iter()
+            .filter(|&&turn| layer_position(turn) == layer_position(next))
+            .count()
+            == 3
+}
+
+/// The number of ways `followed` of the pieces in `slots` places can be placed and twisted.
+fn table_size(slots: usize, followed: usize, twists: usize) -> usize {
+    (slots + 1 - followed..=slots).product::<usize>() * twists.pow(followed as u32)
+}
+
+/// The fewest turns bringing some of the pieces to where they are on a solved cube, which is
+/// never more than solving the whole cube takes. A piece is followed by one of its stickers,
+/// whose facelet gives both the place (or slot) the piece is in and how it is twisted there.
+struct DistanceTable {
+    /// The facelet of a sticker on each followed piece.
+    stickers: Vec<u8>,
+    /// The facelets of each slot the pieces move between, in order of twist.
+    slots: Vec<Vec<u8>>,
+    /// The slot and twist of each facelet in a slot.
+    locations: [(u8, u8); STICKERS],
+    /// The number of ways a piece can be twisted in a slot.
+    twists: usize,
+    distances: Vec<u8>,
+}
+
+impl DistanceTable {
+    /// Fills in the table by a breadth first search backwards from the goals.
+    fn new(group: &Subgroup, slots: &[Vec<u8>], stickers: Vec<u8>, goals: &[Permutation]) -> Self {
+        let mut locations = [(u8::MAX, u8::MAX); STICKERS];
+        for (slot, facelets) in slots.iter().enumerate() {
+            for (twist, &facelet) in facelets.iter().enumerate() {
+                locations[facelet as usize] = (slot as u8, twist as u8);
+            }
+        }
+        let size = table_size(slots.len(), stickers.len(), slots[0].len());
+        let mut table = DistanceTable {
+            stickers,
+            slots: slots.to_vec(),
+            locations,
+            twists: slots[0].len(),
+            distances: vec![u8::MAX; size],
+        };
+
+        let inverses: Vec<Permutation> = group.generators.iter().map(invert).collect();
+        let mut queue = VecDeque::new();
+        for goal in goals {
+            let idx = table.index(goal);
+            if table.distances[idx] == u8::MAX {
+                table.distances[idx] = 0;
+                queue.push_back(idx);
+            }
+        }
+        let mut facelets = [0; 12];
+        while let Some(idx) = queue.pop_front() {
+            let facelets = table.facelets(idx, &mut facelets);
+            let distance = table.distances[idx] + 1;
+            for inverse in inverses.iter() {
+                let next = table.index_of(facelets.iter().map(|&f| inverse[f as usize]));
+                if table.distances[next] == u8::MAX {
+                    table.distances[next] = distance;
+                    queue.push_back(next);
+                }
+            }
+        }
+        table
+    }
+
+    fn distance(&self, perm: &Permutation) -> usize {
+        self.distances[self.index(perm)] as usize
+    }
+
+    fn index(&self, perm: &Permutation) -> usize {
+        self.index_of(self.stickers.iter().map(|&sticker| perm[sticker as usize]))
+    }
+
+    /// Numbers the stickers' locations with one digit per sticker: its slot, ranked among the
+    /// slots no earlier sticker is in, and its twist there.
+    fn index_of(&self, facelets: impl Iterator<Item = u8>) -> usize {
+        let mut idx = 0;
+        let mut used = 0u32;
+        for (k, facelet) in facelets.enumerate() {
+            let (slot, twist) = self.locations[facelet as usize];
+            let rank = slot as usize - (used & ((1 << slot) - 1)).count_ones() as usize;
+            idx = idx * (self.slots.len() - k) * self.twists + rank * self.twists + twist as usize;
+            used |= 1 << slot;
+        }
+        idx
+    }
+
+    /// The facelets the stickers are on in the locations with the given index.
+    fn facelets<'a>(&self, idx: usize, facelets: &'a mut [u8; 12]) -> &'a [u8] {
+        let followed = self.stickers.len();
+        let mut digits = [0; 12];
+        let mut idx = idx;
+        for k in (0..followed).rev() {
+            let radix = (self.slots.len() - k) * self.twists;
+            digits[k] = idx % radix;
+            idx /= radix;
+        }
+
+        let mut used = 0u32;
+        for k in 0..followed {
+            let slot = (0..self.slots.len())
+                .filter(|&slot| used & 1 << slot == 0)
+                .nth(digits[k] / self.twists)
+                .unwrap();
+            used |= 1 << slot;
+            facelets[k] = self.slots[slot][digits[k] % self.twists];
+        }
+        &facelets[..followed]
+    }
+}
+
+/// Solves the cube using only the permitted face turns, in at most twice the fewest turns and
+/// usually the fewest; an error when the cube is invalid or can not be solved with them.
+pub fn solve_with_moves(
+    cube: &CubieCube,
+    permitted_moves: &[Move],
+) -> Result<Vec<Move>, &'static str> {
+    cube.verify()?;
+    let solution = Subgroup::generated_by(permitted_moves).solve(cube)?;
+    Ok(solution
+        .into_iter()
+        .map(|turn| match turn {
+            Turn::Face(action) => action,
+            Turn::Slice(..) => unreachable!("Only face turns are permitted"),
+        })
+        .collect())
+}
+
+/// Solves the cube using only the permitted turns, which may include slice turns (e.g. `<M, U>`),
+/// as `solve_with_moves` does. As slice turns carry the centres around, the facelets are read
+/// with the cube held however it is, and once solved it may be held differently.
+pub fn solve_with_turns(
+    cube: &FaceletCube,
+    permitted_turns: &[Turn],
+) -> Result<Vec<Turn>, &'static str> {
+    let perm = facelets_to_permutation(cube)?;
+    from_permutation(&perm).verify()?;
+    Subgroup::generated_by_turns(permitted_turns).solve_permutation(&perm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+
+    fn random_sequence<T: Copy>(turns: &[T], length: usize) -> Vec<T> {
+        let mut rng = rand::thread_rng();
+        (0..length)
+            .map(|_| *turns.choose(&mut rng).unwrap())
+            .collect()
+    }
+
+    fn turns(notation: &str) -> Vec<Turn> {
+        notation
+            .split_whitespace()
+            .map(|turn| turn.parse().unwrap())
+            .collect()
+    }
+
+    fn apply_turns(perm: Permutation, turns: &[Turn]) -> Permutation {
+        turns.iter().fold(perm, |perm, &turn| {
+            compose(&perm, &to_turn_permutation(turn))
+        })
+    }
+
+    /// The facelets of a cube whose stickers are moved as the permutation describes.
+    fn to_facelets(perm: &Permutation) -> FaceletCube {
+        let solved = FaceletCube::default();
+        let mut faces = solved;
+        for (sticker, &facelet) in perm.iter().enumerate() {
+            faces.state[facelet as usize] = solved.state[sticker];
+        }
+        faces
+    }
+
+    fn order(group: &Subgroup) -> u128 {
+        group
+            .levels
+            .iter()
+            .map(|level| level.transversal.iter().flatten().count() as u128)
+            .product()
+    }
+
+    #[test]
+    fn permutation_follows_moves() {
+        let cube = CubieCube::random(20);
+
+        for action in Move::available().iter() {
+            assert_eq!(
+                to_permutation(cube.apply_move(action)),
+                compose(
+                    &to_permutation(cube),
+                    &to_turn_permutation(Turn::Face(*action))
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn permutation_converts_back_to_cube() {
+        let cube = CubieCube::random(20);
+
+        assert_eq!(24, ROTATIONS.len());
+        for rotation in ROTATIONS.iter() {
+            assert_eq!(
+                cube,
+                from_permutation(&compose(&to_permutation(cube), rotation))
+            );
+        }
+    }
+
+    #[test]
+    fn slice_turns_are_face_turns_with_the_cube_held_differently() {
+        for (slice, faces) in [("M", "R L'"), ("E'", "U' D"), ("S2", "F2 B2")].iter() {
+            assert_eq!(
+                CubieCube::default().apply_moves(
+                    &faces
+                        .split(' ')
+                        .map(|action| action.parse().unwrap())
+                        .collect::<Vec<Move>>()
+                ),
+                from_permutation(&apply_turns(IDENTITY, &turns(slice)))
+            );
+        }
+    }
+
+    #[test]
+    fn random_members_are_members() {
+        let group = Subgroup::generated_by(&[Move(Right, Normal), Move(Up, Normal)]);
+
+        for _ in 0..10 {
+            let cube = group.random();
+            assert!(group.contains(&cube));
+            assert!(cube.verify().is_ok());
+        }
+    }
+
+    #[test]
+    fn subgroup_orders() {
+        assert_eq!(4, order(&Subgroup::generated_by(&[Move(Up, Normal)])));
+        assert_eq!(
+            8,
+            order(&Subgroup::generated_by(&[
+                Move(Up, Normal),
+                Move(Down, Half)
+            ]))
+        );
+        assert_eq!(
+            73_483_200,
+            order(&Subgroup::generated_by(&[
+                Move(Right, Normal),
+                Move(Up, Normal)
+            ]))
+        );
+        assert_eq!(
+            43_252_003_274_489_856_000,
+            order(&Subgroup::generated_by(&Move::available()))
+        );
+        // The slice carries its centres around with its four edges.
+        assert_eq!(4, order(&Subgroup::generated_by_turns(&turns("M"))));
+    }
+
+    #[test]
+    fn unreachable_states_are_not_members() {
+        let group = Subgroup::generated_by(&[Move(Right, Normal), Move(Up, Normal)]);
+        let cube = CubieCube::default().apply_move(&Move(Front, Normal));
+
+        assert!(!group.contains(&cube));
+        assert_eq!(
+            Err("Cube is unreachable using the permitted moves"),
+            solve_with_moves(&cube, &[Move(Right, Normal), Move(Up, Normal)])
+        );
+        assert!(solve_with_turns(&FaceletCube::from(cube), &turns("M U")).is_err());
+    }
+
+    #[test]
+    fn solves_two_generator_states() {
+        let moves = [
+            Move(Right, Normal),
+            Move(Right, Prime),
+            Move(Right, Half),
+            Move(Up, Normal),
+            Move(Up, Prime),
+            Move(Up, Half),
+        ];
+        let cube = CubieCube::default().apply_moves(&random_sequence(&moves, 12));
+        let solution = solve_with_moves(&cube, &moves).unwrap();
+
+        assert!(solution.len() <= 12);
+        assert!(solution.iter().all(|action| moves.contains(action)));
+        assert_eq!(CubieCube::default(), cube.apply_moves(&solution));
+
+        let cube = Subgroup::generated_by(&moves).random();
+        assert_eq!(
+            CubieCube::default(),
+            cube.apply_moves(&solve_with_moves(&cube, &moves).unwrap())
+        );
+    }
+
+    #[test]
+    fn solves_three_generator_states() {
+        let moves = [Move(Right, Normal), Move(Up, Normal), Move(Front, Normal)];
+        let cube = CubieCube::default().apply_moves(&random_sequence(&moves, 12));
+        let solution = solve_with_moves(&cube, &moves).unwrap();
+
+        assert!(solution.iter().all(|action| moves.contains(action)));
+        assert_eq!(CubieCube::default(), cube.apply_moves(&solution));
+    }
+
+    #[test]
+    fn falls_back_to_solutions_at_most_twice_as_long() {
+        let moves = [Move(Right, Normal), Move(Up, Normal)];
+        let group = Subgroup::generated_by(&moves);
+        let start = to_permutation(group.random());
+        let goals = group.goals(&start);
+        let tables = group.distance_tables(&goals);
+
+        let shortest = Search::new(&group, &tables, &goals, 1, usize::MAX)
+            .run(&start)
+            .unwrap();
+        assert!(Search::new(&group, &tables, &goals, 1, 1)
+            .run(&start)
+            .is_none());
+        let solution = Search::new(&group, &tables, &goals, 2, usize::MAX)
+            .run(&start)
+            .unwrap();
+
+        assert!(solution.len() <= 2 * shortest.len());
+        assert!(goals.contains(&apply_turns(start, &solution)));
+    }
+
+    #[test]
+    fn solutions_only_use_permitted_moves() {
+        let moves = [Move(Right, Normal), Move(Up, Normal)];
+        let cube = CubieCube::default().apply_moves(&[Move(Right, Normal), Move(Up, Normal)]);
+        let solution = solve_with_moves(&cube, &moves).unwrap();
+
+        assert_eq!(6, solution.len());
+        assert!(solution.iter().all(|action| moves.contains(action)));
+        assert_eq!(CubieCube::default(), cube.apply_moves(&solution));
+    }
+
+    #[test]
+    fn facelets_convert_to_permutation() {
+        let cube = CubieCube::random(20);
+
+        for rotation in ROTATIONS.iter() {
+            let perm = compose(&to_permutation(cube), rotation);
+            assert_eq!(Ok(perm), facelets_to_permutation(&to_facelets(&perm)));
+        }
+    }
+
+    #[test]
+    fn rejects_impossible_facelets() {
+        // A corner with no U or D sticker, and the U and D centres swapped without turning the
+        // cube over.
+        let mut no_u_or_d = FaceletCube::default();
+        no_u_or_d.state.swap(8, 19);
+        let mut mirrored = FaceletCube::default();
+        mirrored.state.swap(4, 31);
+
+        assert_eq!(
+            Err("Facelets do not form valid pieces"),
+            solve_with_turns(&no_u_or_d, &turns("R U"))
+        );
+        assert_eq!(
+            Err("Centres are not arranged as on a real cube"),
+            solve_with_turns(&mirrored, &turns("R U"))
+        );
+    }
+
+    #[test]
+    fn solves_slice_states() {
+        let permitted = turns("M M' M2 U U' U2");
+        let scramble = random_sequence(&permitted, 16);
+        let scrambled = apply_turns(IDENTITY, &scramble);
+        let solution = solve_with_turns(&to_facelets(&scrambled), &permitted).unwrap();
+
+        assert!(solution.len() <= scramble.len());
+        assert!(solution.iter().all(|turn| permitted.contains(turn)));
+        assert!(ROTATIONS.contains(&apply_turns(scrambled, &solution)));
+    }
+
+    #[test]
+    fn solves_states_with_the_centres_turned() {
+        let scrambled = apply_turns(IDENTITY, &turns("M"));
+        let cube = to_facelets(&scrambled);
+
+        assert_eq!(Ok(turns("M'")), solve_with_turns(&cube, &turns("M M'")));
+        // Turning both outer layers the other way leaves the cube solved, held by x'.
+        assert_eq!(
+            Ok(turns("L R'")),
+            solve_with_turns(&cube, &turns("R R' L L'"))
+        );
+        assert!(solve_with_turns(&cube, &turns("U R")).is_err());
+    }
+}
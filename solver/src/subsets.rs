@@ -0,0 +1,228 @@
+//! Random states in which only some of the pieces are scrambled, for training a single step.
+//!
+//! Each subset's states are chosen uniformly: its pieces are shuffled among their positions and
+//! given random orientations, with the last orientation and (when the permutation parities
+//! differ) a swap of two pieces fixing the state up to be reachable. The two-generator subset is
+//! not described by which pieces move, so is sampled from its stabiliser chain instead.
+
+use rand::prelude::SliceRandom;
+use rand::Rng;
+use std::str::FromStr;
+
+use crate::cubie_cube::{parity, CubieCube};
+use crate::moves::Direction::*;
+use crate::moves::Move;
+use crate::moves::Position::*;
+use crate::pochmann_solver::solve;
+use crate::subgroup::{solve_with_moves, Subgroup};
+
+const ALL_CORNERS: [usize; 8] = [0, 1, 2, 3, 4, 5, 6, 7];
+const ALL_EDGES: [usize; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+const LL_CORNERS: [usize; 4] = [0, 1, 2, 3];
+const LL_EDGES: [usize; 4] = [0, 1, 2, 3];
+/// The last layer along with the front-right slot.
+const LSLL_CORNERS: [usize; 5] = [0, 1, 2, 3, 4];
+const LSLL_EDGES: [usize; 5] = [0, 1, 2, 3, 8];
+/// Every edge except those of the D face's cross.
+const NON_CROSS_EDGES: [usize; 8] = [0, 1, 2, 3, 8, 9, 10, 11];
+const TWO_GEN_MOVES: [Move; 6] = [
+    Move(Right, Normal),
+    Move(Right, Prime),
+    Move(Right, Half),
+    Move(Up, Normal),
+    Move(Up, Prime),
+    Move(Up, Half),
+];
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum Subset {
+    /// The U layer, as left for OLL and PLL.
+    LastLayer,
+    /// The U layer and the front-right F2L slot.
+    LastSlotLastLayer,
+    /// The U layer with its edges oriented.
+    Zbll,
+    /// States reachable by turning only R and U.
+    TwoGen,
+    /// Every edge, with the corners solved.
+    EdgesOnly,
+    /// Every corner, with the edges solved.
+    CornersOnly,
+    /// Everything, with every edge oriented.
+    EdgesOriented,
+    /// Everything except the D face's cross.
+    CrossSolved,
+}
+
+impl FromStr for Subset {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ll" => Ok(Subset::LastLayer),
+            "lsll" => Ok(Subset::LastSlotLastLayer),
+            "zbll" => Ok(Subset::Zbll),
+            "2gen" => Ok(Subset::TwoGen),
+            "edges" => Ok(Subset::EdgesOnly),
+            "corners" => Ok(Subset::CornersOnly),
+            "eo" => Ok(Subset::EdgesOriented),
+            "cross" => Ok(Subset::CrossSolved),
+            _ => Err("Unknown subset"),
+        }
+    }
+}
+
+/// Shuffles the pieces found at `positions`, giving each a random orientation out of `twists`
+/// (1 leaving them oriented) except the last, which makes the orientations add up.
+fn shuffle<P: Copy>(
+    pieces: &mut [P],
+    orientations: &mut [u8],
+    positions: &[usize],
+    twists: u8,
+    rng: &mut impl Rng,
+) {
+    let mut shuffled: Vec<P> = positions.iter().map(|&idx| pieces[idx]).collect();
+    shuffled.shuffle(rng);
+    for (&idx, &piece) in positions.iter().zip(shuffled.iter()) {
+        pieces[idx] = piece;
+    }
+
+    if twists > 1 {
+        for &idx in positions[1..].iter() {
+            orientations[idx] = rng.gen_range(0..twists);
+        }
+        let total: u8 = positions[1..].iter().map(|&idx| orientations[idx]).sum();
+        orientations[positions[0]] = (twists - total % twists) % twists;
+    }
+}
+
+fn random_with(
+    corners: &[usize],
+    edges: &[usize],
+    twist_corners: bool,
+    flip_edges: bool,
+) -> CubieCube {
+    let mut rng = rand::thread_rng();
+    let mut cube = CubieCube::default();
+    let corner_twists = if twist_corners { 3 } else { 1 };
+    let edge_flips = if flip_edges { 2 } else { 1 };
+    shuffle(&mut cube.cp, &mut cube.co, corners, corner_twists, &mut rng);
+    shuffle(&mut cube.ep, &mut cube.eo, edges, edge_flips, &mut rng);
+
+    let corner_parity = parity(&cube.cp.iter().map(|&c| c as u8).collect::<Vec<_>>());
+    let edge_parity = parity(&cube.ep.iter().map(|&e| e as u8).collect::<Vec<_>>());
+    if corner_parity != edge_parity {
+        if edges.len() > 1 {
+            cube.ep.swap(edges[0], edges[1]);
+            cube.eo.swap(edges[0], edges[1]);
+        } else {
+            cube.cp.swap(corners[0], corners[1]);
+            cube.co.swap(corners[0], corners[1]);
+        }
+    }
+    cube
+}
+
+impl CubieCube {
+    /// A uniformly random state of the subset.
+    pub fn random_in(subset: Subset) -> Self {
+        match subset {
+            Subset::LastLayer => random_with(&LL_CORNERS, &LL_EDGES, true, true),
+            Subset::LastSlotLastLayer => random_with(&LSLL_CORNERS, &LSLL_EDGES, true, true),
+            Subset::Zbll => random_with(&LL_CORNERS, &LL_EDGES, true, false),
+            Subset::TwoGen => Subgroup::generated_by(&TWO_GEN_MOVES).random(),
+            Subset::EdgesOnly => random_with(&[], &ALL_EDGES, false, true),
+            Subset::CornersOnly => random_with(&ALL_CORNERS, &[], true, false),
+            Subset::EdgesOriented => random_with(&ALL_CORNERS, &ALL_EDGES, true, false),
+            Subset::CrossSolved => random_with(&ALL_CORNERS, &NON_CROSS_EDGES, true, true),
+        }
+    }
+}
+
+/// A scramble setting up a uniformly random state of the subset, found by solving the state and
+/// inverting the solution.
+pub fn subset_scramble(subset: Subset) -> Vec<Move> {
+    scramble_to(subset, &CubieCube::random_in(subset))
+}
+
+/// Two-generator states are solved using only R and U, so that their scrambles are too.
+fn scramble_to(subset: Subset, cube: &CubieCube) -> Vec<Move> {
+    let solution = match subset {
+        Subset::TwoGen => solve_with_moves(cube, &TWO_GEN_MOVES).ok(),
+        _ => solve(cube),
+    };
+    solution
+        .expect("Subset states are solvable")
+        .iter()
+        .rev()
+        .map(|action| action.inverse())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+
+    fn moved_corners(cube: &CubieCube) -> Vec<usize> {
+        (0..8)
+            .filter(|&idx| cube.cp[idx] as usize != idx || cube.co[idx] != 0)
+            .collect()
+    }
+
+    fn moved_edges(cube: &CubieCube) -> Vec<usize> {
+        (0..12)
+            .filter(|&idx| cube.ep[idx] as usize != idx || cube.eo[idx] != 0)
+            .collect()
+    }
+
+    #[test]
+    fn subsets_only_scramble_their_pieces() {
+        for _ in 0..20 {
+            let ll = CubieCube::random_in(Subset::LastLayer);
+            let lsll = CubieCube::random_in(Subset::LastSlotLastLayer);
+            let zbll = CubieCube::random_in(Subset::Zbll);
+            let edges = CubieCube::random_in(Subset::EdgesOnly);
+            let corners = CubieCube::random_in(Subset::CornersOnly);
+            let eo = CubieCube::random_in(Subset::EdgesOriented);
+            let cross = CubieCube::random_in(Subset::CrossSolved);
+
+            for cube in [ll, lsll, zbll, edges, corners, eo, cross].iter() {
+                assert!(cube.verify().is_ok());
+            }
+            assert!(moved_corners(&ll).iter().all(|c| LL_CORNERS.contains(c)));
+            assert!(moved_edges(&ll).iter().all(|e| LL_EDGES.contains(e)));
+            assert!(moved_corners(&lsll)
+                .iter()
+                .all(|c| LSLL_CORNERS.contains(c)));
+            assert!(moved_edges(&lsll).iter().all(|e| LSLL_EDGES.contains(e)));
+            assert!(moved_edges(&zbll).iter().all(|e| LL_EDGES.contains(e)));
+            assert!(zbll.eo.iter().all(|&eo| eo == 0));
+            assert!(moved_corners(&edges).is_empty());
+            assert!(moved_edges(&corners).is_empty());
+            assert!(eo.eo.iter().all(|&eo| eo == 0));
+            assert!(moved_edges(&cross)
+                .iter()
+                .all(|e| NON_CROSS_EDGES.contains(e)));
+        }
+    }
+
+    #[test]
+    fn two_gen_scrambles_only_turn_r_and_u() {
+        let cube = CubieCube::random_in(Subset::TwoGen);
+        let scramble = scramble_to(Subset::TwoGen, &cube);
+
+        assert!(scramble.iter().all(|action| TWO_GEN_MOVES.contains(action)));
+        assert_eq!(cube, CubieCube::default().apply_moves(&scramble));
+    }
+
+    #[test]
+    fn scrambles_set_up_the_subset() {
+        let scramble = subset_scramble("ll".parse().unwrap());
+        let cube = CubieCube::default().apply_moves(&scramble);
+
+        assert!(moved_corners(&cube).iter().all(|c| LL_CORNERS.contains(c)));
+        assert!(moved_edges(&cube).iter().all(|e| LL_EDGES.contains(e)));
+        assert!("f2l".parse::<Subset>().is_err());
+    }
+}
@@ -0,0 +1,357 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::facelet_cube::FaceletCube;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum View {
+    Net,
+    Isometric,
+}
+
+impl FromStr for View {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "net" => Ok(View::Net),
+            "isometric" => Ok(View::Isometric),
+            _ => Err("Unknown view"),
+        }
+    }
+}
+
+/// Which stickers are shown in colour, the rest being drawn in the masked colour.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Stickering {
+    Full,
+    Cross,
+    F2l,
+    Oll,
+    Ll,
+}
+
+impl FromStr for Stickering {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Stickering::Full),
+            "cross" => Ok(Stickering::Cross),
+            "f2l" => Ok(Stickering::F2l),
+            "oll" => Ok(Stickering::Oll),
+            "ll" => Ok(Stickering::Ll),
+            _ => Err("Unknown stickering"),
+        }
+    }
+}
+
+impl Stickering {
+    fn is_shown(self, facelet: usize) -> bool {
+        let (face, sticker) = (facelet / 9, facelet % 9);
+        let is_side = face != 0 && face != 3;
+
+        match self {
+            Stickering::Full => true,
+            Stickering::Cross => match face {
+                3 => sticker % 2 == 1 || sticker == 4,
+                0 => false,
+                _ => sticker == 4 || sticker == 7,
+            },
+            Stickering::F2l => face == 3 || (is_side && sticker >= 3),
+            Stickering::Oll => face == 0,
+            Stickering::Ll => face == 0 || (is_side && sticker < 3),
+        }
+    }
+}
+
+/// An arrow drawn between the centres of two stickers, written as their facelet names (e.g.
+/// "U1U3"), as used for showing PLL cases.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Arrow {
+    pub from: usize,
+    pub to: usize,
+}
+
+fn parse_facelet(s: &str) -> Result<usize, &'static str> {
+    let mut chars = s.chars();
+    let face = match chars.next() {
+        Some('U') => 0,
+        Some('R') => 1,
+        Some('F') => 2,
+        Some('D') => 3,
+        Some('L') => 4,
+        Some('B') => 5,
+        _ => return Err("Unknown facelet"),
+    };
+    match chars.as_str().parse::<usize>() {
+        Ok(sticker) if (1..=9).contains(&sticker) => Ok(face * 9 + sticker - 1),
+        _ => Err("Unknown facelet"),
+    }
+}
+
+impl FromStr for Arrow {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 4 || !s.is_ascii() {
+            return Err("Arrows must be given as two facelets, e.g. U1U3");
+        }
+        Ok(Arrow {
+            from: parse_facelet(&s[0..2])?,
+            to: parse_facelet(&s[2..4])?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    pub view: View,
+    /// The fill colour of each face, given in URFDLB order.
+    pub colours: [String; 6],
+    pub masked_colour: String,
+    pub stickering: Stickering,
+    pub arrows: Vec<Arrow>,
+    pub size: u32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            view: View::Net,
+            colours: [
+                "#f7f5f5".to_string(),
+                "red".to_string(),
+                "green".to_string(),
+                "yellow".to_string(),
+                "orange".to_string(),
+                "blue".to_string(),
+            ],
+            masked_colour: "#808080".to_string(),
+            stickering: Stickering::Full,
+            arrows: vec![],
+            size: 240,
+        }
+    }
+}
+
+type Point = (f64, f64);
+
+fn net_sticker(facelet: usize) -> [Point; 4] {
+    let (fx, fy) = match facelet / 9 {
+        0 => (3., 0.),
+        1 => (6., 3.),
+        2 => (3., 3.),
+        3 => (3., 6.),
+        4 => (0., 3.),
+        _ => (9., 3.),
+    };
+    let x = fx + (facelet % 3) as f64;
+    let y = fy + (facelet % 9 / 3) as f64;
+
+    [(x, y), (x + 1., y), (x + 1., y + 1.), (x, y + 1.)]
+}
+
+fn isometric_sticker(facelet: usize) -> Option<[Point; 4]> {
+    let (row, col) = ((facelet % 9 / 3) as f64, (facelet % 3) as f64);
+    let corners = match facelet / 9 {
+        0 => [
+            (col, 3., row),
+            (col + 1., 3., row),
+            (col + 1., 3., row + 1.),
+            (col, 3., row + 1.),
+        ],
+        1 => [
+            (3., 3. - row, 3. - col),
+            (3., 3. - row, 2. - col),
+            (3., 2. - row, 2. - col),
+            (3., 2. - row, 3. - col),
+        ],
+        2 => [
+            (col, 3. - row, 3.),
+            (col + 1., 3. - row, 3.),
+            (col + 1., 2. - row, 3.),
+            (col, 2. - row, 3.),
+        ],
+        _ => return None,
+    };
+
+    let project = |(x, y, z): (f64, f64, f64)| ((x - z) * 0.75f64.sqrt(), (x + z) * 0.5 - y);
+    Some([
+        project(corners[0]),
+        project(corners[1]),
+        project(corners[2]),
+        project(corners[3]),
+    ])
+}
+
+fn centre(sticker: &[Point; 4]) -> Point {
+    (
+        sticker.iter().map(|p| p.0).sum::<f64>() / 4.,
+        sticker.iter().map(|p| p.1).sum::<f64>() / 4.,
+    )
+}
+
+/// Escapes a caller supplied value (e.g. a colour) for use inside a double quoted attribute.
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders a facelet cube as an SVG image, either as an unfolded net or as an isometric view of
+/// the U, F and R faces.
+pub fn render(cube: &FaceletCube, options: &SvgOptions) -> String {
+    let stickers: Vec<Option<[Point; 4]>> = (0..54)
+        .map(|facelet| match options.view {
+            View::Net => Some(net_sticker(facelet)),
+            View::Isometric => isometric_sticker(facelet),
+        })
+        .collect();
+    let (view_box, height) = match options.view {
+        View::Net => ("-0.1 -0.1 12.2 9.2", u64::from(options.size) * 3 / 4),
+        View::Isometric => ("-2.7 -3.1 5.4 6.2", u64::from(options.size) * 31 / 27),
+    };
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{}">"#,
+        options.size, height, view_box
+    );
+    svg.push_str(concat!(
+        r#"<defs><marker id="arrowhead" viewBox="0 0 10 10" refX="8" refY="5" "#,
+        r#"markerWidth="4" markerHeight="4" orient="auto-start-reverse">"#,
+        r#"<path d="M0,0 L10,5 L0,10 z"/></marker></defs>"#
+    ));
+
+    for (facelet, sticker) in stickers.iter().enumerate() {
+        if let Some(sticker) = sticker {
+            let fill = if options.stickering.is_shown(facelet) {
+                &options.colours[cube.state[facelet] as usize]
+            } else {
+                &options.masked_colour
+            };
+            let points = sticker
+                .iter()
+                .map(|(x, y)| format!("{:.3},{:.3}", x, y))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let _ = write!(
+                svg,
+                r#"<polygon points="{}" fill="{}" stroke="black" stroke-width="0.05"/>"#,
+                points,
+                escape_attribute(fill)
+            );
+        }
+    }
+
+    for arrow in options.arrows.iter() {
+        if let (Some(from), Some(to)) = (&stickers[arrow.from], &stickers[arrow.to]) {
+            let (from, to) = (centre(from), centre(to));
+            let _ = write!(
+                svg,
+                concat!(
+                    r#"<line x1="{:.3}" y1="{:.3}" x2="{:.3}" y2="{:.3}" stroke="black" "#,
+                    r#"stroke-width="0.1" marker-end="url(#arrowhead)"/>"#
+                ),
+                from.0, from.1, to.0, to.1
+            );
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn net_draws_every_sticker() {
+        let svg = render(&FaceletCube::default(), &SvgOptions::default());
+
+        assert_eq!(54, svg.matches("<polygon").count());
+        assert_eq!(9, svg.matches(r#"fill="green""#).count());
+    }
+
+    #[test]
+    fn colours_can_not_inject_markup() {
+        let mut options = SvgOptions::default();
+        options.colours[2] = r#"red"/><script>alert(1)</script><x y=""#.to_string();
+        let svg = render(&FaceletCube::default(), &options);
+
+        assert!(!svg.contains("<script"));
+        assert_eq!(54, svg.matches("<polygon").count());
+        assert_eq!(
+            9,
+            svg.matches(r#"fill="red&quot;/&gt;&lt;script&gt;"#).count()
+        );
+    }
+
+    #[test]
+    fn large_sizes_do_not_overflow() {
+        let svg = render(
+            &FaceletCube::default(),
+            &SvgOptions {
+                view: View::Isometric,
+                size: u32::MAX,
+                ..SvgOptions::default()
+            },
+        );
+
+        assert!(svg.contains(r#"width="4294967295" height="4931258746""#));
+    }
+
+    #[test]
+    fn isometric_view_draws_visible_faces() {
+        let svg = render(
+            &FaceletCube::default(),
+            &SvgOptions {
+                view: View::Isometric,
+                ..SvgOptions::default()
+            },
+        );
+
+        assert_eq!(27, svg.matches("<polygon").count());
+        assert_eq!(0, svg.matches(r#"fill="yellow""#).count());
+    }
+
+    #[test]
+    fn stickering_masks_stickers() {
+        let svg = render(
+            &FaceletCube::default(),
+            &SvgOptions {
+                stickering: Stickering::Oll,
+                ..SvgOptions::default()
+            },
+        );
+
+        assert_eq!(45, svg.matches(r##"fill="#808080""##).count());
+    }
+
+    #[test]
+    fn arrows_are_drawn_between_sticker_centres() {
+        let svg = render(
+            &FaceletCube::default(),
+            &SvgOptions {
+                arrows: vec!["U1U3".parse().unwrap(), "U3U1".parse().unwrap()],
+                ..SvgOptions::default()
+            },
+        );
+
+        assert!(svg.contains(r#"<line x1="3.500" y1="0.500" x2="5.500" y2="0.500""#));
+        assert_eq!(2, svg.matches("<line").count());
+        assert!("U0U3".parse::<Arrow>().is_err());
+    }
+}
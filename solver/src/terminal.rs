@@ -0,0 +1,100 @@
+use std::fmt::Write;
+
+use crate::facelet_cube::{Colour, FaceletCube};
+use crate::facelet_format::ColourScheme;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TerminalStyle {
+    /// Stickers are drawn as blocks of ANSI 256-colour backgrounds.
+    Ansi,
+    /// Stickers are written as their colour letters, for terminals (and logs) without colour.
+    Plain,
+}
+
+/// The 256-colour palette index of each face's colour, given in URFDLB order.
+const ANSI_COLOURS: [u8; 6] = [231, 196, 34, 226, 208, 21];
+
+/// The faces drawn on each row of the net, as (column, face) pairs.
+const NET_ROWS: [&[(usize, Colour)]; 3] = [
+    &[(1, Colour::U)],
+    &[
+        (0, Colour::L),
+        (1, Colour::F),
+        (2, Colour::R),
+        (3, Colour::B),
+    ],
+    &[(1, Colour::D)],
+];
+
+fn sticker(colour: Colour, style: TerminalStyle) -> String {
+    match style {
+        TerminalStyle::Ansi => format!("\x1b[48;5;{}m  \x1b[0m", ANSI_COLOURS[colour as usize]),
+        TerminalStyle::Plain => format!("{} ", ColourScheme::WESTERN.to_char(colour)),
+    }
+}
+
+/// Renders a facelet cube as an unfolded net for printing to a terminal, with the U face above
+/// the L, F, R and B faces and the D face below.
+pub fn render(cube: &FaceletCube, style: TerminalStyle) -> String {
+    let blank = "  ".repeat(3);
+    let mut net = String::new();
+
+    for faces in NET_ROWS.iter() {
+        for row in 0..3 {
+            let mut line = String::new();
+            let mut column = 0;
+            for &(face_column, face) in faces.iter() {
+                while column < face_column {
+                    line.push_str(&blank);
+                    column += 1;
+                }
+                for col in 0..3 {
+                    line.push_str(&sticker(
+                        cube.state[face as usize * 9 + row * 3 + col],
+                        style,
+                    ));
+                }
+                column += 1;
+            }
+            let _ = writeln!(net, "{}", line.trim_end());
+        }
+    }
+
+    net
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Cube;
+    use crate::moves::{Direction, Move, Position};
+
+    #[test]
+    fn plain_net_shows_colour_letters() {
+        let cube = FaceletCube::default().apply_move(&Move(Position::Right, Direction::Normal));
+
+        assert_eq!(
+            concat!(
+                "      W W G\n",
+                "      W W G\n",
+                "      W W G\n",
+                "O O O G G Y R R R W B B\n",
+                "O O O G G Y R R R W B B\n",
+                "O O O G G Y R R R W B B\n",
+                "      Y Y B\n",
+                "      Y Y B\n",
+                "      Y Y B\n",
+            ),
+            render(&cube, TerminalStyle::Plain)
+        );
+    }
+
+    #[test]
+    fn ansi_net_colours_every_sticker() {
+        let net = render(&FaceletCube::default(), TerminalStyle::Ansi);
+
+        assert_eq!(54, net.matches("\x1b[48;5;").count());
+        assert_eq!(9, net.matches("\x1b[48;5;34m").count());
+        assert_eq!(9, net.lines().count());
+    }
+}
@@ -0,0 +1,452 @@
+//! A typed JavaScript API: a `Cube` class whose methods return objects rather than strings,
+//! described by the TypeScript definitions below, and which throws `CubeError`s carrying a
+//! machine readable `code` alongside their message.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::cube::Cube;
+use crate::cubie_cube::CubieCube;
+use crate::facelet_cube::FaceletCube;
+use crate::moves::{Direction, Move, MoveSequence};
+use crate::pochmann_solver::{solve_in_phases, IncrementalSolve, Solution, SolveStatus};
+use crate::subgroup::solve_with_moves;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT_DEFINITIONS: &'static str = r#"
+export type CubeErrorCode =
+  | "INVALID_FACELETS"
+  | "INVALID_CUBIES"
+  | "INVALID_MOVE"
+  | "UNSOLVABLE_CUBE"
+  | "UNREACHABLE_CUBE";
+
+/** Thrown by every fallible `Cube` method. */
+export interface CubeError extends Error {
+  name: "CubeError";
+  code: CubeErrorCode;
+}
+
+/** Space separated moves (e.g. "R U R' U'") or one move per element. */
+export type Moves = string | string[];
+
+/** The piece held in each position and its orientation, in URF, UFL, ... order. */
+export interface Cubies {
+  cp: number[];
+  co: number[];
+  ep: number[];
+  eo: number[];
+}
+
+export interface SolutionPhase {
+  description: string;
+  /** The subgroup the cube is brought into by the end of the phase. */
+  subgroup: string;
+  moves: string[];
+  /** The cube's facelets once the phase's moves have been applied. */
+  cube: string;
+}
+
+export interface SolutionMetrics {
+  /** Half turn metric, counting every face turn as one move. */
+  htm: number;
+  /** Quarter turn metric, counting half turns as two moves. */
+  qtm: number;
+}
+
+export interface Solution {
+  /** Every phase's moves, with moves of the same face across phase boundaries merged. */
+  moves: string[];
+  phases: SolutionPhase[];
+  /** The indices of the phases each merged move originates from. */
+  movePhases: number[][];
+  metrics: SolutionMetrics;
+}
+
+export interface SolveProgress {
+  done: boolean;
+  /** The number of phases solved so far, out of four. */
+  phasesSolved: number;
+  /** The number of states explored by the current phase's search. */
+  statesExpanded: number;
+  /** Present once done. */
+  solution?: Solution;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Moves")]
+    pub type Moves;
+
+    #[wasm_bindgen(typescript_type = "string[]")]
+    pub type MoveList;
+
+    #[wasm_bindgen(typescript_type = "Cubies")]
+    pub type Cubies;
+
+    #[wasm_bindgen(typescript_type = "Solution")]
+    pub type TypedSolution;
+
+    #[wasm_bindgen(typescript_type = "SolveProgress")]
+    pub type TypedSolveProgress;
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ErrorCode {
+    InvalidFacelets,
+    InvalidCubies,
+    InvalidMove,
+    /// The cube can not be reached from solved.
+    UnsolvableCube,
+    /// The cube can not be solved using only the permitted moves.
+    UnreachableCube,
+}
+
+impl ErrorCode {
+    fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InvalidFacelets => "INVALID_FACELETS",
+            ErrorCode::InvalidCubies => "INVALID_CUBIES",
+            ErrorCode::InvalidMove => "INVALID_MOVE",
+            ErrorCode::UnsolvableCube => "UNSOLVABLE_CUBE",
+            ErrorCode::UnreachableCube => "UNREACHABLE_CUBE",
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct CubeError(ErrorCode, &'static str);
+
+impl From<CubeError> for JsValue {
+    fn from(CubeError(code, message): CubeError) -> Self {
+        let error = js_sys::Error::new(message);
+        error.set_name("CubeError");
+        js_sys::Reflect::set(&error, &"code".into(), &code.as_str().into()).unwrap();
+        error.into()
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Metrics {
+    htm: usize,
+    qtm: usize,
+}
+
+impl Metrics {
+    fn of(moves: &[Move]) -> Self {
+        Metrics {
+            htm: moves.len(),
+            qtm: moves
+                .iter()
+                .map(|action| match action.1 {
+                    Direction::Half => 2,
+                    _ => 1,
+                })
+                .sum(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SolutionWithMetrics {
+    #[serde(flatten)]
+    solution: Solution,
+    metrics: Metrics,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SolveProgress<'a> {
+    done: bool,
+    phases_solved: usize,
+    states_expanded: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solution: Option<&'a SolutionWithMetrics>,
+}
+
+fn to_js<T: Serialize>(value: &T) -> JsValue {
+    js_sys::JSON::parse(&serde_json::to_string(value).unwrap()).unwrap()
+}
+
+fn parse_moves(moves: &str) -> Result<Vec<Move>, CubeError> {
+    moves
+        .parse::<MoveSequence>()
+        .map(|sequence| sequence.0)
+        .map_err(|err| CubeError(ErrorCode::InvalidMove, err))
+}
+
+fn to_moves(moves: &JsValue) -> Result<Vec<Move>, CubeError> {
+    if let Some(moves) = moves.as_string() {
+        return parse_moves(&moves);
+    }
+    match moves.dyn_ref::<js_sys::Array>() {
+        Some(moves) => moves
+            .iter()
+            .map(|action| match action.as_string() {
+                Some(action) => action
+                    .parse()
+                    .map_err(|err| CubeError(ErrorCode::InvalidMove, err)),
+                None => Err(CubeError(ErrorCode::InvalidMove, "Moves must be strings")),
+            })
+            .collect(),
+        None => Err(CubeError(
+            ErrorCode::InvalidMove,
+            "Moves must be given as a string or an array of strings",
+        )),
+    }
+}
+
+/// The moves as an array of their notation, e.g. `["R", "U'"]`.
+pub(crate) fn to_js_moves<T: std::fmt::Display>(moves: &[T]) -> js_sys::Array {
+    moves
+        .iter()
+        .map(|action| JsValue::from_str(&action.to_string()))
+        .collect()
+}
+
+fn to_move_list(moves: &[Move]) -> MoveList {
+    to_js_moves(moves).unchecked_into()
+}
+
+fn verified(cube: &FaceletCube) -> Result<CubieCube, CubeError> {
+    cube.verify()
+        .map_err(|err| CubeError(ErrorCode::UnsolvableCube, err))?;
+    Ok(CubieCube::from(*cube))
+}
+
+fn solution(cube: &FaceletCube) -> Result<SolutionWithMetrics, CubeError> {
+    let solution = solve_in_phases(&verified(cube)?)
+        .ok_or(CubeError(ErrorCode::UnsolvableCube, "Cube has no solution"))?;
+    Ok(SolutionWithMetrics {
+        metrics: Metrics::of(&solution.moves),
+        solution,
+    })
+}
+
+fn solution_with(cube: &FaceletCube, permitted_moves: &[Move]) -> Result<Vec<Move>, CubeError> {
+    solve_with_moves(&verified(cube)?, permitted_moves)
+        .map_err(|err| CubeError(ErrorCode::UnreachableCube, err))
+}
+
+/// An immutable cube; methods which change the cube return a new one.
+#[wasm_bindgen(js_name = Cube)]
+#[derive(Clone)]
+pub struct JsCube(FaceletCube);
+
+#[wasm_bindgen(js_class = Cube)]
+impl JsCube {
+    /// The solved cube.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsCube {
+        JsCube(FaceletCube::default())
+    }
+
+    /// A cube scrambled by the given number of random moves (100 by default).
+    pub fn random(moves: Option<u8>) -> JsCube {
+        JsCube(FaceletCube::random(moves.unwrap_or(100)))
+    }
+
+    /// Parses the cube's 54 facelets, without checking it can be reached from solved.
+    #[wasm_bindgen(js_name = fromFacelets)]
+    pub fn from_facelets(facelets: &str) -> Result<JsCube, JsValue> {
+        Ok(JsCube(facelets.parse().map_err(|err| {
+            CubeError(ErrorCode::InvalidFacelets, err)
+        })?))
+    }
+
+    #[wasm_bindgen(js_name = fromCubies)]
+    pub fn from_cubies(cubies: Cubies) -> Result<JsCube, JsValue> {
+        let cube = js_sys::JSON::stringify(&cubies)
+            .ok()
+            .and_then(|json| json.as_string())
+            .ok_or(CubeError(
+                ErrorCode::InvalidCubies,
+                "Invalid cubie cube representation",
+            ))?
+            .parse::<CubieCube>()
+            .map_err(|err| CubeError(ErrorCode::InvalidCubies, err))?;
+        cube.verify()
+            .map_err(|err| CubeError(ErrorCode::UnsolvableCube, err))?;
+        Ok(JsCube(FaceletCube::from(cube)))
+    }
+
+    pub fn apply(&self, moves: Moves) -> Result<JsCube, JsValue> {
+        Ok(JsCube(self.0.apply_moves(&to_moves(&moves)?)))
+    }
+
+    pub fn solve(&self) -> Result<TypedSolution, JsValue> {
+        Ok(to_js(&solution(&self.0)?).unchecked_into())
+    }
+
+    /// Finds a short solution using only the permitted face turns. Slice moves such as M are
+    /// not supported and throw an `INVALID_MOVE` error.
+    #[wasm_bindgen(js_name = solveWith)]
+    pub fn solve_with(&self, permitted_moves: Moves) -> Result<MoveList, JsValue> {
+        Ok(to_move_list(&solution_with(
+            &self.0,
+            &to_moves(&permitted_moves)?,
+        )?))
+    }
+
+    /// Throws an `UNSOLVABLE_CUBE` error if the cube can not be reached from solved.
+    pub fn validate(&self) -> Result<(), JsValue> {
+        verified(&self.0)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = isSolved)]
+    pub fn is_solved(&self) -> bool {
+        self.0 == FaceletCube::default()
+    }
+
+    #[wasm_bindgen(js_name = toFacelets)]
+    pub fn to_facelets(&self) -> String {
+        self.0.to_string()
+    }
+
+    #[wasm_bindgen(js_name = toCubies)]
+    pub fn to_cubies(&self) -> Result<Cubies, JsValue> {
+        Ok(to_js(&verified(&self.0)?).unchecked_into())
+    }
+
+    /// The cube which undoes this one's scramble when applied to it.
+    pub fn inverse(&self) -> Result<JsCube, JsValue> {
+        Ok(JsCube(FaceletCube::from(verified(&self.0)?.inverse())))
+    }
+
+    pub fn equals(&self, other: &JsCube) -> bool {
+        self.0 == other.0
+    }
+}
+
+/// Solves a cube a bounded amount of work at a time, so that a Web Worker can report progress
+/// (and notice cancellation) between steps; abandon a solve by freeing the solver.
+#[wasm_bindgen]
+pub struct IncrementalSolver {
+    solve: IncrementalSolve,
+    solution: Option<SolutionWithMetrics>,
+}
+
+impl IncrementalSolver {
+    fn start(cube: &FaceletCube) -> Result<Self, CubeError> {
+        Ok(IncrementalSolver {
+            solve: IncrementalSolve::new(&verified(cube)?),
+            solution: None,
+        })
+    }
+
+    fn advance(&mut self, budget: usize) -> Result<SolveProgress<'_>, CubeError> {
+        if self.solution.is_none() {
+            match self.solve.step(budget) {
+                SolveStatus::Pending => {}
+                SolveStatus::Solved(solution) => {
+                    self.solution = Some(SolutionWithMetrics {
+                        metrics: Metrics::of(&solution.moves),
+                        solution,
+                    })
+                }
+                SolveStatus::Unsolvable => {
+                    return Err(CubeError(ErrorCode::UnsolvableCube, "Cube has no solution"))
+                }
+            }
+        }
+
+        Ok(SolveProgress {
+            done: self.solution.is_some(),
+            phases_solved: self.solve.phases_solved(),
+            states_expanded: self.solve.states_expanded(),
+            solution: self.solution.as_ref(),
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl IncrementalSolver {
+    /// Throws an `UNSOLVABLE_CUBE` error if the cube can not be reached from solved.
+    #[wasm_bindgen(constructor)]
+    pub fn new(cube: &JsCube) -> Result<IncrementalSolver, JsValue> {
+        Ok(IncrementalSolver::start(&cube.0)?)
+    }
+
+    /// Explores at most `budget` states (descending a precomputed phase table counts as one)
+    /// before returning; once done, the solution is returned by every further step.
+    pub fn step(&mut self, budget: u32) -> Result<TypedSolveProgress, JsValue> {
+        Ok(to_js(&self.advance(budget as usize)?).unchecked_into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn solutions_include_metrics() {
+        let cube = FaceletCube::default().apply_moves(&parse_moves("R2 U F'").unwrap());
+        let solution = solution(&cube).unwrap();
+
+        assert_eq!(
+            FaceletCube::default(),
+            cube.apply_moves(&solution.solution.moves)
+        );
+        assert_eq!(Metrics::of(&solution.solution.moves), solution.metrics);
+        assert_eq!(
+            Metrics { htm: 3, qtm: 4 },
+            Metrics::of(&parse_moves("R2 U F'").unwrap())
+        );
+
+        let json = serde_json::to_value(&solution).unwrap();
+        assert!(json["movePhases"].is_array());
+        assert_eq!(solution.metrics.htm, json["metrics"]["htm"]);
+    }
+
+    #[test]
+    fn errors_carry_codes() {
+        let twisted: FaceletCube = "WWWWWWWWGWRRRRRRRRGGRGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            Err(CubeError(ErrorCode::InvalidMove, "Unknown move")),
+            parse_moves("R X").map(|_| ())
+        );
+        assert_eq!(
+            CubeError(ErrorCode::UnsolvableCube, "Corner twist is invalid"),
+            solution(&twisted).err().unwrap()
+        );
+
+        let cube = FaceletCube::default().apply_moves(&parse_moves("R U").unwrap());
+        assert!(solution_with(&cube, &parse_moves("R U").unwrap()).is_ok());
+        assert_eq!(
+            ErrorCode::UnreachableCube,
+            solution_with(&cube, &parse_moves("R F").unwrap())
+                .unwrap_err()
+                .0
+        );
+    }
+
+    #[test]
+    fn incremental_solver_reports_progress_until_done() {
+        let cube = FaceletCube::random(50);
+        let mut solver = IncrementalSolver::start(&cube).unwrap();
+
+        let mut steps = 0;
+        let solution = loop {
+            steps += 1;
+            let progress = solver.advance(1).unwrap();
+            assert!(progress.phases_solved <= 4);
+            if progress.done {
+                break progress.solution.unwrap().solution.moves.clone();
+            }
+        };
+
+        assert!(steps > 1);
+        assert_eq!(FaceletCube::default(), cube.apply_moves(&solution));
+        assert_eq!(
+            json!(solution),
+            serde_json::to_value(solver.advance(1).unwrap()).unwrap()["solution"]["moves"]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+/* Exercises the C API; build and run with `make ffi-test`. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "rubik_cube_solver.h"
+
+#define SOLVED "WWWWWWWWWRRRRRRRRRGGGGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB"
+#define TWISTED "WWWWWWWWGWRRRRRRRRGGRGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB"
+
+static int failures = 0;
+
+#define CHECK(condition)                                                   \
+    do {                                                                   \
+        if (!(condition)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,        \
+                    __LINE__, #condition);                                 \
+            failures++;                                                    \
+        }                                                                  \
+    } while (0)
+
+static void test_scramble_and_solve(void) {
+    RcsCube *cube = NULL;
+    char *solution = NULL;
+    char *facelets = NULL;
+
+    CHECK(rcs_cube_parse(SOLVED, &cube) == RCS_STATUS_OK);
+    CHECK(rcs_cube_apply_moves(cube, "R U R' U' F2 D B'") == RCS_STATUS_OK);
+    CHECK(rcs_cube_validate(cube) == RCS_STATUS_OK);
+
+    CHECK(rcs_cube_solve(cube, NULL, &solution) == RCS_STATUS_OK);
+    CHECK(rcs_cube_apply_moves(cube, solution) == RCS_STATUS_OK);
+
+    facelets = rcs_cube_to_string(cube);
+    CHECK(strcmp(facelets, SOLVED) == 0);
+
+    rcs_string_free(facelets);
+    rcs_string_free(solution);
+    rcs_cube_free(cube);
+}
+
+static void test_solve_with_permitted_moves(void) {
+    RcsCube *cube = rcs_cube_new();
+    RcsSolveOptions options = {"R R' R2 U U' U2"};
+    char *solution = NULL;
+
+    CHECK(rcs_cube_apply_moves(cube, "R U R' U R U2 R'") == RCS_STATUS_OK);
+    CHECK(rcs_cube_solve(cube, &options, &solution) == RCS_STATUS_OK);
+    CHECK(strchr(solution, 'F') == NULL);
+    rcs_string_free(solution);
+
+    CHECK(rcs_cube_apply_moves(cube, "F") == RCS_STATUS_OK);
+    CHECK(rcs_cube_solve(cube, &options, &solution) == RCS_STATUS_UNREACHABLE_CUBE);
+
+    rcs_cube_free(cube);
+}
+
+static void test_errors(void) {
+    RcsCube *cube = NULL;
+    char *solution = NULL;
+
+    CHECK(rcs_cube_parse("WWW", &cube) == RCS_STATUS_INVALID_FACELETS);
+    CHECK(strcmp(rcs_last_error(), "Invalid facelet cube representation") == 0);
+
+    CHECK(rcs_cube_parse(TWISTED, &cube) == RCS_STATUS_OK);
+    CHECK(rcs_cube_validate(cube) == RCS_STATUS_UNSOLVABLE_CUBE);
+    CHECK(rcs_cube_solve(cube, NULL, &solution) == RCS_STATUS_UNSOLVABLE_CUBE);
+    CHECK(rcs_cube_apply_moves(cube, "R X") == RCS_STATUS_INVALID_MOVE);
+    CHECK(rcs_cube_apply_moves(NULL, "R") == RCS_STATUS_NULL_ARGUMENT);
+
+    rcs_cube_free(cube);
+}
+
+int main(void) {
+    test_scramble_and_solve();
+    test_solve_with_permitted_moves();
+    test_errors();
+
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    printf("All checks passed\n");
+    return 0;
+}
@@ -0,0 +1,91 @@
+import pickle
+import unittest
+
+from rubik_cube_solver import CubieCube, FaceletCube, Move, parse_moves, scramble
+
+SOLVED = "WWWWWWWWWRRRRRRRRRGGGGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB"
+
+
+class MoveTest(unittest.TestCase):
+    def test_parses_moves(self):
+        self.assertEqual([Move("R"), Move("U'"), Move("F2")], parse_moves("R U' F2"))
+        self.assertEqual(("R", -1), (Move("R'").face, Move("R'").turns))
+        self.assertEqual(Move("R'"), Move("R").inverse())
+
+    def test_rejects_invalid_moves(self):
+        with self.assertRaises(ValueError):
+            Move("X")
+
+    def test_scrambles_never_repeat_a_face(self):
+        moves = scramble(30)
+        self.assertEqual(30, len(moves))
+        self.assertTrue(all(a.face != b.face for a, b in zip(moves, moves[1:])))
+
+
+class CubieCubeTest(unittest.TestCase):
+    def test_solves_cubes(self):
+        cube = CubieCube.random(50)
+        self.assertTrue(cube.apply(cube.solve()).is_solved())
+
+    def test_solves_with_permitted_moves(self):
+        cube = CubieCube().apply("R U")
+        self.assertTrue(all(m.face in "RU" for m in cube.solve_with(["R", "U"])))
+        with self.assertRaises(ValueError):
+            cube.apply("F").solve_with("R U")
+
+    def test_group_operations(self):
+        sexy = CubieCube().apply([Move("R"), "U", "R'", "U'"])
+        self.assertEqual(6, sexy.order())
+        self.assertTrue((sexy * sexy.inverse()).is_solved())
+        self.assertEqual(sexy * sexy, sexy ** 2)
+        self.assertEqual(sexy.inverse(), sexy ** -1)
+        self.assertEqual(sexy ** 4, sexy ** -(2 ** 63))
+
+    def test_order_is_bounded(self):
+        self.assertEqual(1, CubieCube().order())
+        self.assertEqual(1260, CubieCube().apply("R U2 D' B D'").order())
+        self.assertTrue((CubieCube().apply("R U2 D' B D'") ** 1260).is_solved())
+
+    def test_pickles(self):
+        cube = CubieCube.random(20)
+        self.assertEqual(cube, pickle.loads(pickle.dumps(cube)))
+        self.assertEqual(cube, CubieCube.from_json(cube.to_json()))
+
+    def test_rejects_unreachable_cubes(self):
+        twisted = CubieCube().to_json().replace('"co":[0', '"co":[1', 1)
+        with self.assertRaisesRegex(ValueError, "Corner twist is invalid"):
+            CubieCube.from_json(twisted)
+        with self.assertRaises(ValueError):
+            CubieCube().__setstate__(twisted)
+
+    def test_converts_to_facelets(self):
+        cube = CubieCube().apply("R")
+        self.assertEqual(FaceletCube().apply("R"), cube.to_facelet_cube())
+        self.assertEqual(cube, cube.to_facelet_cube().to_cubie_cube())
+
+
+class FaceletCubeTest(unittest.TestCase):
+    def test_parses_facelets(self):
+        self.assertEqual(SOLVED, str(FaceletCube(SOLVED)))
+        self.assertEqual(FaceletCube(), FaceletCube(SOLVED))
+        with self.assertRaises(ValueError):
+            FaceletCube("WWW")
+
+    def test_verifies_cubes(self):
+        twisted = FaceletCube("WWWWWWWWGWRRRRRRRRGGRGGGGGGYYYYYYYYYOOOOOOOOOBBBBBBBBB")
+        with self.assertRaisesRegex(ValueError, "Corner twist is invalid"):
+            twisted.verify()
+        with self.assertRaises(ValueError):
+            twisted.solve()
+
+    def test_solves_cubes(self):
+        cube = FaceletCube.random(50)
+        self.assertTrue(cube.apply(cube.solve()).is_solved())
+
+    def test_pickles(self):
+        cube = FaceletCube.random(20)
+        self.assertEqual(cube, pickle.loads(pickle.dumps(cube)))
+
+
+if __name__ == "__main__":
+    unittest.main()